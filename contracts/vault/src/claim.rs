@@ -1,8 +1,17 @@
 use cosmwasm_std::{
-    attr, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    attr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128,
 };
 
-use crate::querier::{get_balance_by_share, get_total_balance, get_vault_balance};
+use crate::deposit::release_principal;
+use crate::fee::{
+    accrue_management_fee, collect_fee, get_early_withdraw_penalty_rate, pay_management_fee,
+    redistribute_penalty,
+};
+use crate::insurance::apply_principal_index;
+use crate::querier::{
+    get_balance_by_share, get_excluded_balance, get_total_balance, get_vault_balance,
+};
 use crate::state::{
     read_config, read_deposit_info, read_state, store_deposit_info,
     store_state, Config, DepositInfo,
@@ -19,11 +28,18 @@ pub fn claim_yield(
     let config = read_config(deps.storage)?;
     let mut deposit_info = read_deposit_info(deps.storage, &info.sender)?;
 
-    if deposit_info.maturity > env.block.time.seconds() {
-        return Err(StdError::generic_err("Still locked"));
-    }
+    let penalty_rate =
+        get_early_withdraw_penalty_rate(&config, &deposit_info, env.block.time.seconds())?;
 
     let mut state = read_state(deps.storage)?;
+    apply_principal_index(&state, &mut deposit_info)?;
+    accrue_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
     let is_principal_unclaimed = deposit_info.principal - deposit_info.principal_claimed > Uint128::zero();
     
     if is_principal_unclaimed {
@@ -32,6 +48,7 @@ pub fn claim_yield(
             config.clone(),
             state.clone(),
             deposit_info.clone(),
+            env.block.time.seconds(),
         )?;
 
         if loss || yield_amount <= deposit_info.yield_claimed {
@@ -57,33 +74,50 @@ pub fn claim_yield(
     if is_principal_unclaimed {
         deposit_info.current_amount = deposit_info.current_amount.checked_sub(claimable)?;
 
-        let total_balance = get_total_balance(deps.as_ref(), config.clone())?.checked_sub(state.total_subsidized)?;
+        let total_balance = get_total_balance(deps.as_ref(), config.clone())?.checked_sub(
+            get_excluded_balance(&config, &state, env.block.time.seconds()),
+        )?;
         let claimable_share = state.total_share * Decimal::from_ratio(claimable, total_balance);
         
         deposit_info.share = deposit_info.share.checked_sub(claimable_share)?;
         state.total_share = state.total_share.checked_sub(claimable_share)?;
+        state.total_boosted_share = state
+            .total_boosted_share
+            .saturating_sub(claimable_share * deposit_info.boost);
     } else {
         state.total_subsidized = state.total_subsidized.checked_sub(claimable)?;
     }
 
+    let fee_messages = pay_management_fee(deps.as_ref(), &config, &mut state, claimable)?;
+
+    let penalty = claimable * penalty_rate;
+    redistribute_penalty(&config, &mut state, penalty, env.block.time.seconds());
+
     store_state(deps.storage, &state)?;
     store_deposit_info(deps.storage, &info.sender, &deposit_info)?;
 
     let asset = Asset {
         info: AssetInfo::NativeToken {
-            denom: config.stable_denom,
+            denom: config.stable_denom.clone(),
         },
-        amount: claimable,
+        amount: claimable.checked_sub(penalty)?,
     };
 
+    let mut messages: Vec<CosmosMsg> = vec![asset.into_msg(&deps.querier, info.sender)?];
+    messages.extend(collect_fee(
+        deps.as_ref(),
+        &config,
+        penalty,
+        &config.penalty_recipient,
+    )?);
+    messages.extend(fee_messages);
+
     Ok(Response::new()
-        .add_messages(vec![asset.into_msg(
-            &deps.querier,
-            info.sender,
-        )?])
+        .add_messages(messages)
         .add_attributes(vec![
             attr("action", "claim_yield"),
             attr("amount", claimable),
+            attr("penalty", penalty),
         ]))
 }
 
@@ -96,11 +130,18 @@ pub fn claim_principal(
     let config = read_config(deps.storage)?;
     let mut deposit_info = read_deposit_info(deps.storage, &info.sender)?;
 
-    if deposit_info.maturity > env.block.time.seconds() {
-        return Err(StdError::generic_err("Still locked"));
-    }
+    let penalty_rate =
+        get_early_withdraw_penalty_rate(&config, &deposit_info, env.block.time.seconds())?;
     
     let mut state = read_state(deps.storage)?;
+    apply_principal_index(&state, &mut deposit_info)?;
+    accrue_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
     let is_principal_unclaimed = deposit_info.principal - deposit_info.principal_claimed > Uint128::zero();
     
     if is_principal_unclaimed {
@@ -109,6 +150,7 @@ pub fn claim_principal(
             config.clone(),
             state.clone(),
             deposit_info.clone(),
+            env.block.time.seconds(),
         )?;
         
         if loss || yield_amount <= deposit_info.yield_claimed {
@@ -127,38 +169,62 @@ pub fn claim_principal(
         return Err(StdError::generic_err("Insufficient"));
     }
 
+    release_principal(
+        &mut state,
+        deposit_info
+            .principal
+            .checked_sub(deposit_info.principal_claimed)?,
+        deposit_info.boost,
+    );
     deposit_info.principal_claimed += claimable;
     
     if is_principal_unclaimed {
         deposit_info.current_amount = deposit_info.current_amount.checked_sub(claimable)?;
 
-        let total_balance = get_total_balance(deps.as_ref(), config.clone())?.checked_sub(state.total_subsidized)?;
+        let total_balance = get_total_balance(deps.as_ref(), config.clone())?.checked_sub(
+            get_excluded_balance(&config, &state, env.block.time.seconds()),
+        )?;
         let claimable_share = state.total_share * Decimal::from_ratio(claimable, total_balance);
 
         deposit_info.share = deposit_info.share.checked_sub(claimable_share)?;
         state.total_share = state.total_share.checked_sub(claimable_share)?;
+        state.total_boosted_share = state
+            .total_boosted_share
+            .saturating_sub(claimable_share * deposit_info.boost);
     } else {
         state.total_subsidized = state.total_subsidized.checked_sub(claimable)?;
     }
 
+    let fee_messages = pay_management_fee(deps.as_ref(), &config, &mut state, claimable)?;
+
+    let penalty = claimable * penalty_rate;
+    redistribute_penalty(&config, &mut state, penalty, env.block.time.seconds());
+
     store_state(deps.storage, &state)?;
     store_deposit_info(deps.storage, &info.sender, &deposit_info)?;
 
     let asset = Asset {
         info: AssetInfo::NativeToken {
-            denom: config.stable_denom,
+            denom: config.stable_denom.clone(),
         },
-        amount: claimable,
+        amount: claimable.checked_sub(penalty)?,
     };
 
+    let mut messages: Vec<CosmosMsg> = vec![asset.into_msg(&deps.querier, info.sender)?];
+    messages.extend(collect_fee(
+        deps.as_ref(),
+        &config,
+        penalty,
+        &config.penalty_recipient,
+    )?);
+    messages.extend(fee_messages);
+
     Ok(Response::new()
-        .add_messages(vec![asset.into_msg(
-            &deps.querier,
-            info.sender,
-        )?])
+        .add_messages(messages)
         .add_attributes(vec![
             attr("action", "claim_principal"),
             attr("amount", claimable),
+            attr("penalty", penalty),
         ]))
 }
 
@@ -167,8 +233,19 @@ pub fn get_updated_yield(
     config: Config,
     state: State,
     deposit_info: DepositInfo,
+    time: u64,
 ) -> StdResult<(Uint128, Uint128, bool)> {
-    let amount = get_balance_by_share(deps, config, state, deposit_info.share)?;
+    let amount = get_balance_by_share(
+        deps,
+        config,
+        state,
+        deposit_info.share,
+        deposit_info.boost,
+        deposit_info
+            .principal
+            .checked_sub(deposit_info.principal_claimed)?,
+        time,
+    )?;
     let mut loss = false;
 
     // if lose == true, yield_amount indicates lost amount
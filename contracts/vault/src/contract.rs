@@ -8,13 +8,19 @@ use cosmwasm_std::{
 
 use crate::claim::{claim_principal, claim_yield};
 use crate::deposit::{deposit_native, withdraw};
-use crate::manage::{invest, update_config};
+use crate::insurance::{declare_loss, fund_insurance};
+use crate::manage::{
+    invest, migrate_strategy, migrate_strategy_hook, report, update_config, update_strategies,
+};
+use crate::migration::migrate_legacy_storage;
 use crate::querier::{
-    query_available, query_config, query_deposit_info, query_state,
+    query_available, query_config, query_deposit_info, query_early_withdraw_penalty, query_fees,
+    query_lock_tiers, query_preview_deposit, query_preview_withdraw, query_retired_strategies,
+    query_state, query_strategies, query_strategy_credit, query_strategy_debt,
     query_total_balance, query_vault_balance,
 };
 use crate::state::{store_config, store_state, Config};
-use athena::vault::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, State};
+use athena::vault::{ExecuteMsg, FeeRecipient, InstantiateMsg, MigrateMsg, QueryMsg, State};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -34,11 +40,22 @@ pub fn instantiate(
         &Config {
             contract_addr: deps.api.addr_canonicalize(&env.contract.address.as_str())?,
             controller: deps.api.addr_canonicalize(&msg.controller)?,
-            strategy: None,
             stable_denom: msg.stable_denom,
             invest_percentage: msg.invest_percentage,
             lock_period: msg.lock_period,
             force_withdraw: false,
+            early_withdraw: false,
+            early_withdraw_penalty: Decimal::zero(),
+            penalty_recipient: FeeRecipient::Depositors,
+            lock_tiers: vec![],
+            auto_invest: false,
+            auto_invest_threshold: Uint128::zero(),
+            migration_tolerance: Decimal::percent(1u64),
+            profit_unlock_period: 21600u64,
+            management_fee: Decimal::zero(),
+            deposit_fee: Decimal::zero(),
+            withdraw_fee: Decimal::zero(),
+            fee_recipient: FeeRecipient::Treasury,
         },
     )?;
 
@@ -46,7 +63,17 @@ pub fn instantiate(
         deps.storage,
         &State {
             total_share: Uint128::zero(),
+            total_boosted_share: Uint128::zero(),
+            total_principal: Uint128::zero(),
+            total_boosted_principal: Uint128::zero(),
             total_subsidized: Uint128::zero(),
+            insurance_reserve: Uint128::zero(),
+            principal_index: Decimal::one(),
+            locked_profit: Uint128::zero(),
+            last_report: env.block.time.seconds(),
+            management_fee_payable: Uint128::zero(),
+            management_fee_paid: Uint128::zero(),
+            last_fee_accrual: env.block.time.seconds(),
         },
     )?;
 
@@ -58,32 +85,82 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
     match msg {
         ExecuteMsg::UpdateConfig {
             controller,
-            strategy,
             invest_percentage,
             lock_period,
             force_withdraw,
+            early_withdraw,
+            early_withdraw_penalty,
+            penalty_recipient,
+            lock_tiers,
+            auto_invest,
+            auto_invest_threshold,
+            migration_tolerance,
+            profit_unlock_period,
+            management_fee,
+            deposit_fee,
+            withdraw_fee,
+            fee_recipient,
         } => update_config(
             deps,
             info,
             controller,
-            strategy,
             invest_percentage,
             lock_period,
             force_withdraw,
+            early_withdraw,
+            early_withdraw_penalty,
+            penalty_recipient,
+            lock_tiers,
+            auto_invest,
+            auto_invest_threshold,
+            migration_tolerance,
+            profit_unlock_period,
+            management_fee,
+            deposit_fee,
+            withdraw_fee,
+            fee_recipient,
         ),
-        ExecuteMsg::Deposit {} => deposit_native(deps, env, info),
+        ExecuteMsg::UpdateStrategies { strategies } => {
+            update_strategies(deps, env, info, strategies)
+        }
+        ExecuteMsg::Deposit { lock_period } => deposit_native(deps, env, info, lock_period),
         ExecuteMsg::Withdraw {
             withdraw_amount,
             force_withdraw,
         } => withdraw(deps, env, info, withdraw_amount, force_withdraw),
         ExecuteMsg::ClaimYield {} => claim_yield(deps, env, info),
         ExecuteMsg::ClaimPrincipal {} => claim_principal(deps, env, info),
-        ExecuteMsg::Invest {} => invest(deps.as_ref(), info),
+        ExecuteMsg::Invest {} => invest(deps, info),
+        ExecuteMsg::FundInsurance {} => fund_insurance(deps, info),
+        ExecuteMsg::DeclareLoss { amount } => declare_loss(deps, env, info, amount),
+        ExecuteMsg::MigrateStrategy {
+            strategy,
+            new_strategy,
+        } => migrate_strategy(deps.as_ref(), env, info, strategy, new_strategy),
+        ExecuteMsg::MigrateStrategyHook {
+            strategy,
+            new_strategy,
+            expected_amount,
+            previous_balance,
+        } => migrate_strategy_hook(
+            deps,
+            env,
+            info,
+            strategy,
+            new_strategy,
+            expected_amount,
+            previous_balance,
+        ),
+        ExecuteMsg::Report {
+            gain,
+            loss,
+            debt_payment,
+        } => report(deps, env, info, gain, loss, debt_payment),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::DepositInfo { addr } => to_binary(&query_deposit_info(deps, addr)?),
@@ -91,10 +168,25 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::TotalBalance {} => to_binary(&query_total_balance(deps)?),
         QueryMsg::Available {} => to_binary(&query_available(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::EarlyWithdrawPenalty { addr } => {
+            to_binary(&query_early_withdraw_penalty(deps, env, addr)?)
+        }
+        QueryMsg::LockTiers {} => to_binary(&query_lock_tiers(deps)?),
+        QueryMsg::RetiredStrategies {} => to_binary(&query_retired_strategies(deps)?),
+        QueryMsg::Strategies {} => to_binary(&query_strategies(deps)?),
+        QueryMsg::StrategyDebt { strategy } => to_binary(&query_strategy_debt(deps, strategy)?),
+        QueryMsg::StrategyCredit { strategy } => {
+            to_binary(&query_strategy_credit(deps, strategy)?)
+        }
+        QueryMsg::Fees {} => to_binary(&query_fees(deps, env)?),
+        QueryMsg::PreviewDeposit { amount } => to_binary(&query_preview_deposit(deps, env, amount)?),
+        QueryMsg::PreviewWithdraw { addr, amount } => {
+            to_binary(&query_preview_withdraw(deps, env, addr, amount)?)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    migrate_legacy_storage(deps, env, msg.start_after, msg.limit)
 }
@@ -1,19 +1,27 @@
 use cosmwasm_std::{
-    attr, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128, Decimal
+    attr, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128, Decimal
 };
 use crate::claim::get_updated_yield;
-use crate::querier::{get_total_balance, get_vault_balance};
+use crate::fee::{
+    accrue_management_fee, collect_fee, get_early_withdraw_penalty_rate, pay_management_fee,
+    redistribute_penalty,
+};
+use crate::insurance::apply_principal_index;
+use crate::manage::get_auto_invest_messages;
+use crate::querier::{get_share_by_amount, get_vault_balance};
 use crate::state::{
-    read_config, read_deposit_info, read_state, store_deposit_info, store_state,
-    Config, DepositInfo,
+    read_config, read_deposit_info, read_state, read_strategies, store_deposit_info,
+    store_state, Config, DepositInfo,
 };
 use athena::asset::{Asset, AssetInfo};
+use athena::vault::State;
 
 /// Deposit UST and update total share
 pub fn deposit_native(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    lock_period: Option<u64>,
 ) -> StdResult<Response> {
     let config: Config = read_config(deps.storage)?;
     
@@ -34,7 +42,26 @@ pub fn deposit_native(
         return Err(StdError::generic_err("Deposit amount must be greater than 0"));
     }
 
+    let (lock_period, boost) = match lock_period {
+        None => (config.lock_period, Decimal::one()),
+        Some(lock_period) => {
+            let lock_tier = config
+                .lock_tiers
+                .iter()
+                .find(|tier| tier.lock_period == lock_period)
+                .ok_or_else(|| StdError::generic_err("Invalid lock tier"))?;
+            (lock_tier.lock_period, lock_tier.boost)
+        }
+    };
+
     let mut state = read_state(deps.storage)?;
+    accrue_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        deposit_amount,
+    )?;
 
     let mut deposit_info = match read_deposit_info(deps.storage, &info.sender) {
         Ok(info) => info,
@@ -42,40 +69,91 @@ pub fn deposit_native(
             principal: Uint128::zero(),
             current_amount: Uint128::zero(),
             share: Uint128::zero(),
+            boost: Decimal::one(),
+            lock_period: u64::MIN,
             maturity: u64::MIN,
             yield_amount: Uint128::zero(),
             yield_claimed: Uint128::zero(),
             principal_claimed: Uint128::zero(),
+            principal_index: state.principal_index,
         }
     };
 
-    deposit_info.maturity = config.lock_period + env.block.time.seconds();
+    apply_principal_index(&state, &mut deposit_info)?;
 
-    let total_balance = get_total_balance(deps.as_ref(), config)?.checked_sub(deposit_amount)?;
+    // Boost is weighted into the totals by the deposit, so it is fixed while principal is left
+    if deposit_info.principal > deposit_info.principal_claimed && deposit_info.boost != boost {
+        return Err(StdError::generic_err(
+            "Cannot change lock tier of an existing deposit",
+        ));
+    }
 
-    deposit_info.current_amount += deposit_amount;
-    deposit_info.principal += deposit_amount;
-    deposit_info.share = if state.total_share.is_zero() || total_balance <= state.total_subsidized {
-        deposit_amount
-    } else {
-        state.total_share
-            * Decimal::from_ratio(
-                deposit_amount,
-                total_balance.checked_sub(state.total_subsidized)?,
-            )
-    };
+    // A new deposit can not shorten the lock of the existing one
+    let maturity = lock_period + env.block.time.seconds();
+    if maturity >= deposit_info.maturity {
+        deposit_info.maturity = maturity;
+        deposit_info.lock_period = lock_period;
+    }
+
+    // Fee left in the vault is shared by the existing depositors
+    let deposit_fee = deposit_amount * config.deposit_fee;
+    let principal = deposit_amount.checked_sub(deposit_fee)?;
+
+    deposit_info.current_amount += principal;
+    deposit_info.principal += principal;
+    deposit_info.share = get_share_by_amount(
+        deps.as_ref(),
+        config.clone(),
+        &state,
+        principal,
+        deposit_amount,
+        env.block.time.seconds(),
+    )?;
+
+    deposit_info.boost = boost;
 
     state.total_share = state.total_share + deposit_info.share;
+    state.total_boosted_share += deposit_info.share * boost;
+    state.total_principal += principal;
+    state.total_boosted_principal += principal * boost;
     
     store_deposit_info(deps.storage, &info.sender, &deposit_info)?;
     store_state(deps.storage, &state)?;
 
-    Ok(Response::new().add_attributes(vec![
+    let mut logs = vec![
         attr("action", "deposit"),
         attr("amount", deposit_amount),
+        attr("deposit_fee", deposit_fee),
         attr("share", deposit_info.share),
         attr("maturity", deposit_info.maturity.to_string()),
-    ]))
+    ];
+
+    let (invest_amount, mut messages) = get_auto_invest_messages(deps.branch(), config.clone())?;
+    if !invest_amount.is_zero() {
+        logs.push(attr("auto_invest", invest_amount));
+    }
+
+    let mut fee_messages = collect_fee(
+        deps.as_ref(),
+        &config,
+        deposit_fee,
+        &config.fee_recipient,
+    )?;
+    let fee_outgoing = if fee_messages.is_empty() {
+        Uint128::zero()
+    } else {
+        deposit_fee
+    };
+    fee_messages.extend(pay_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        invest_amount + fee_outgoing,
+    )?);
+    messages.extend(fee_messages);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(logs))
 }
 
 /// Check withdrawable amount and execute withdraw_to_user
@@ -89,11 +167,18 @@ pub fn withdraw(
     let config = read_config(deps.storage)?;
     let mut deposit_info = read_deposit_info(deps.storage, &info.sender)?;
 
-    if deposit_info.maturity > env.block.time.seconds() {
-        return Err(StdError::generic_err("Still locked"));
-    }
+    let penalty_rate =
+        get_early_withdraw_penalty_rate(&config, &deposit_info, env.block.time.seconds())?;
     
     let mut state = read_state(deps.storage)?;
+    apply_principal_index(&state, &mut deposit_info)?;
+    accrue_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
     let is_principal_unclaimed = deposit_info.principal - deposit_info.principal_claimed > Uint128::zero();
 
     if !is_principal_unclaimed {
@@ -105,6 +190,7 @@ pub fn withdraw(
         config.clone(),
         state.clone(),
         deposit_info.clone(),
+        env.block.time.seconds(),
     )?;
     
     deposit_info.current_amount = amount;
@@ -148,13 +234,16 @@ pub fn withdraw(
         let mut available_withdraw = withdraw_amount;
     
         if vault_balance < available_withdraw {
-            if config.strategy.is_none() || (force_withdraw && config.force_withdraw) {
+            if read_strategies(deps.storage)?.is_empty() || (force_withdraw && config.force_withdraw) {
                 available_withdraw = vault_balance;
             } else {
                 return Err(StdError::generic_err("Insufficient"));
             }
         }
         
+        let total_share = state.total_share;
+        let principal_claimed = deposit_info.principal_claimed;
+
         if available_withdraw <= principal_withdraw {
             deposit_info.principal_claimed += available_withdraw;
             state.total_share = state.total_share * Decimal::from_ratio(
@@ -169,30 +258,68 @@ pub fn withdraw(
             state.total_share = state.total_share.checked_sub(deposit_info.share)?;
         }
 
+        state.total_boosted_share = state.total_boosted_share.saturating_sub(
+            total_share.saturating_sub(state.total_share) * deposit_info.boost,
+        );
+        release_principal(
+            &mut state,
+            deposit_info
+                .principal_claimed
+                .checked_sub(principal_claimed)?,
+            deposit_info.boost,
+        );
+
         let unclaimed = deposit_info
             .clone()
             .current_amount
             .checked_sub(available_withdraw)?;
         state.total_subsidized += unclaimed;
     
+        let fee_messages =
+            pay_management_fee(deps.as_ref(), &config, &mut state, available_withdraw)?;
+
+        let penalty = available_withdraw * penalty_rate;
+        redistribute_penalty(&config, &mut state, penalty, env.block.time.seconds());
+
         store_state(deps.storage, &state)?;
         store_deposit_info(deps.storage, &info.sender, &deposit_info)?;
+        let withdraw_fee = available_withdraw.checked_sub(penalty)? * config.withdraw_fee;
     
         let asset = Asset {
             info: AssetInfo::NativeToken {
-                denom: config.stable_denom,
+                denom: config.stable_denom.clone(),
             },
-            amount: available_withdraw,
+            amount: available_withdraw.checked_sub(penalty + withdraw_fee)?,
         };
+
+        let mut messages: Vec<CosmosMsg> = vec![asset.into_msg(&deps.querier, info.sender)?];
+        messages.extend(collect_fee(
+            deps.as_ref(),
+            &config,
+            penalty,
+            &config.penalty_recipient,
+        )?);
+        messages.extend(collect_fee(
+            deps.as_ref(),
+            &config,
+            withdraw_fee,
+            &config.fee_recipient,
+        )?);
+        messages.extend(fee_messages);
     
         Ok(Response::new()
-            .add_messages(vec![asset.into_msg(
-                &deps.querier,
-                info.sender,
-            )?])
+            .add_messages(messages)
             .add_attributes(vec![
                 attr("action", "withdraw"),
                 attr("amount", available_withdraw),
+                attr("penalty", penalty),
+                attr("withdraw_fee", withdraw_fee),
             ]))
     }
 }
+
+/// Remove claimed principal of a deposit from the principal totals
+pub fn release_principal(state: &mut State, amount: Uint128, boost: Decimal) {
+    state.total_principal = state.total_principal.saturating_sub(amount);
+    state.total_boosted_principal = state.total_boosted_principal.saturating_sub(amount * boost);
+}
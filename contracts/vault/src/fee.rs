@@ -0,0 +1,143 @@
+use cosmwasm_std::{Addr, CosmosMsg, Decimal, Deps, Fraction, StdError, StdResult, Uint128};
+
+use crate::querier::{
+    get_excluded_balance, get_locked_profit, get_total_balance, get_vault_balance,
+};
+use crate::state::{Config, DepositInfo};
+use athena::asset::{Asset, AssetInfo};
+use athena::querier::query_treasury;
+use athena::vault::{FeeRecipient, State};
+
+const SECONDS_PER_YEAR: u64 = 31536000u64;
+
+/// Penalty rate for exiting before maturity
+/// Decays linearly from `early_withdraw_penalty` at deposit time to zero at maturity
+pub fn compute_early_withdraw_penalty_rate(
+    config: &Config,
+    deposit_info: &DepositInfo,
+    current_time: u64,
+) -> Decimal {
+    if deposit_info.maturity <= current_time || config.early_withdraw_penalty.is_zero() {
+        return Decimal::zero();
+    }
+
+    let remaining = deposit_info.maturity - current_time;
+
+    if deposit_info.lock_period == 0 || remaining >= deposit_info.lock_period {
+        config.early_withdraw_penalty
+    } else {
+        Decimal::from_ratio(
+            config.early_withdraw_penalty.numerator() * remaining as u128,
+            config.early_withdraw_penalty.denominator() * deposit_info.lock_period as u128,
+        )
+    }
+}
+
+/// Return penalty rate of exiting now,
+/// or an error if the deposit is locked and early withdraw is disabled
+pub fn get_early_withdraw_penalty_rate(
+    config: &Config,
+    deposit_info: &DepositInfo,
+    current_time: u64,
+) -> StdResult<Decimal> {
+    if deposit_info.maturity > current_time && !config.early_withdraw {
+        return Err(StdError::generic_err("Still locked"));
+    }
+
+    Ok(compute_early_withdraw_penalty_rate(
+        config,
+        deposit_info,
+        current_time,
+    ))
+}
+
+/// Lock penalty left in the vault for remaining depositors as profit,
+/// released to their shares over the profit unlock period
+pub fn redistribute_penalty(
+    config: &Config,
+    state: &mut State,
+    penalty: Uint128,
+    current_time: u64,
+) {
+    if penalty.is_zero() || config.penalty_recipient != FeeRecipient::Depositors {
+        return;
+    }
+
+    state.locked_profit = get_locked_profit(config, state, current_time) + penalty;
+    state.last_report = current_time;
+}
+
+/// Send collected fee to treasury,
+/// or leave it in the vault for remaining depositors
+pub fn collect_fee(
+    deps: Deps,
+    config: &Config,
+    amount: Uint128,
+    recipient: &FeeRecipient,
+) -> StdResult<Vec<CosmosMsg>> {
+    if amount.is_zero() || *recipient == FeeRecipient::Depositors {
+        return Ok(vec![]);
+    }
+
+    let fee_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: config.stable_denom.clone(),
+        },
+        amount,
+    };
+
+    Ok(vec![fee_asset.into_msg(
+        &deps.querier,
+        Addr::unchecked(query_treasury(
+            &deps.querier,
+            deps.api.addr_humanize(&config.controller)?,
+        )?),
+    )?])
+}
+
+/// Accrue management fee on the share pool since the last accrual
+/// `inflow` is the amount received by the current transaction, which is not in the pool yet
+pub fn accrue_management_fee(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    current_time: u64,
+    inflow: Uint128,
+) -> StdResult<()> {
+    let elapsed = current_time.saturating_sub(state.last_fee_accrual);
+    state.last_fee_accrual = current_time;
+
+    if elapsed == 0 || config.management_fee.is_zero() || state.total_share.is_zero() {
+        return Ok(());
+    }
+
+    let pool_balance = get_total_balance(deps, config.clone())?
+        .saturating_sub(get_excluded_balance(config, state, current_time) + inflow);
+    let fee = pool_balance.multiply_ratio(elapsed, SECONDS_PER_YEAR) * config.management_fee;
+
+    state.management_fee_payable += fee;
+
+    Ok(())
+}
+
+/// Pay accrued management fee to treasury,
+/// up to the vault balance not used by the current transaction
+pub fn pay_management_fee(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    outgoing: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    if state.management_fee_payable.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let available = get_vault_balance(deps, config.clone())?
+        .saturating_sub(state.insurance_reserve + state.total_subsidized + outgoing);
+    let amount = std::cmp::min(state.management_fee_payable, available);
+
+    state.management_fee_payable = state.management_fee_payable.checked_sub(amount)?;
+    state.management_fee_paid += amount;
+
+    collect_fee(deps, config, amount, &FeeRecipient::Treasury)
+}
@@ -0,0 +1,130 @@
+use cosmwasm_std::{
+    attr, Decimal, DepsMut, Env, Fraction, MessageInfo, Response, StdError, StdResult, Uint128,
+};
+
+use crate::fee::accrue_management_fee;
+use crate::querier::{get_excluded_balance, get_total_balance};
+use crate::state::{read_config, read_state, store_state, DepositInfo};
+use athena::access_control::assert_governance_privilege;
+use athena::vault::State;
+
+/// Add sent underlying to the insurance reserve
+pub fn fund_insurance(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    let amount: Uint128 = info
+        .funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(Uint128::zero);
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Fund amount must be greater than 0"));
+    }
+
+    let mut state = read_state(deps.storage)?;
+    state.insurance_reserve += amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "fund_insurance"),
+        attr("amount", amount),
+        attr("insurance_reserve", state.insurance_reserve),
+    ]))
+}
+
+/// Cover a realized loss with the insurance reserve,
+/// and write down the principal of all deposits by the uncovered part.
+/// The loss cannot exceed the shortfall of the pool balance against the principal
+pub fn declare_loss(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_governance_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        &info.sender.to_string(),
+    )?;
+
+    let mut state = read_state(deps.storage)?;
+    accrue_management_fee(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
+
+    if state.total_share.is_zero() {
+        return Err(StdError::generic_err("Nothing to write down"));
+    }
+
+    let excluded_balance = get_excluded_balance(&config, &state, env.block.time.seconds());
+    let pool_balance = get_total_balance(deps.as_ref(), config)?.saturating_sub(excluded_balance);
+
+    let shortfall = state.total_principal.saturating_sub(pool_balance);
+    if amount > shortfall {
+        return Err(StdError::generic_err(format!(
+            "Loss exceeds shortfall of {}",
+            shortfall
+        )));
+    }
+
+    let covered = std::cmp::min(amount, state.insurance_reserve);
+    let haircut = amount.checked_sub(covered)?;
+    state.insurance_reserve = state.insurance_reserve.checked_sub(covered)?;
+
+    if !haircut.is_zero() {
+        let pool_balance = pool_balance + covered;
+        if pool_balance.is_zero() {
+            return Err(StdError::generic_err("Loss exceeds vault balance"));
+        }
+
+        state.principal_index = Decimal::from_ratio(
+            Uint128::from(state.principal_index.numerator())
+                .multiply_ratio(pool_balance, pool_balance + haircut),
+            state.principal_index.denominator(),
+        );
+        state.total_principal = state
+            .total_principal
+            .multiply_ratio(pool_balance, pool_balance + haircut);
+        state.total_boosted_principal = state
+            .total_boosted_principal
+            .multiply_ratio(pool_balance, pool_balance + haircut);
+    }
+
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "declare_loss"),
+        attr("amount", amount),
+        attr("covered", covered),
+        attr("haircut", haircut),
+        attr("principal_index", state.principal_index.to_string()),
+    ]))
+}
+
+/// Write down unclaimed principal of the deposit by losses declared since its last update
+pub fn apply_principal_index(state: &State, deposit_info: &mut DepositInfo) -> StdResult<()> {
+    if deposit_info.principal_index == state.principal_index {
+        return Ok(());
+    }
+
+    let unclaimed = deposit_info
+        .principal
+        .checked_sub(deposit_info.principal_claimed)?;
+
+    deposit_info.principal = deposit_info.principal_claimed
+        + unclaimed.multiply_ratio(
+            state.principal_index.numerator(),
+            deposit_info.principal_index.numerator(),
+        );
+    deposit_info.principal_index = state.principal_index;
+
+    Ok(())
+}
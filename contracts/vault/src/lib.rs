@@ -1,7 +1,10 @@
 pub mod claim;
 pub mod contract;
 pub mod deposit;
+pub mod fee;
+pub mod insurance;
 pub mod manage;
+pub mod migration;
 pub mod querier;
 pub mod state;
 
@@ -1,19 +1,46 @@
-use cosmwasm_std::{attr, Decimal, Deps, DepsMut, MessageInfo, Response, StdError, StdResult};
+use cosmwasm_std::{
+    attr, to_binary, CanonicalAddr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Uint128, WasmMsg,
+};
 
-use crate::querier::get_available;
-use crate::state::{read_config, store_config};
-use athena::access_control::{assert_access_privilege, assert_governance_privilege};
+use crate::fee::{accrue_management_fee, pay_management_fee};
+use crate::querier::{
+    get_available, get_invest_allocations, get_locked_profit, get_vault_balance,
+    query_strategy_total_balance,
+};
+use crate::state::{
+    read_config, read_retired_strategies, read_state, read_strategies, store_config,
+    store_retired_strategies, store_state, store_strategies, Config, RetiredStrategy,
+    StrategyInfoRaw,
+};
+use athena::access_control::{
+    assert_access_privilege, assert_governance_privilege, assert_sender_privilege,
+};
 use athena::asset::{Asset, AssetInfo};
+use athena::vault::{ExecuteMsg, FeeRecipient, LockTier, StrategyInfo};
+use athena::vault_strategy::ExecuteMsg as StrategyExecuteMsg;
 
 /// Update vault configuration
+#[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
     controller: Option<String>,
-    strategy: Option<String>,
     invest_percentage: Option<Decimal>,
     lock_period: Option<u64>,
     force_withdraw: Option<bool>,
+    early_withdraw: Option<bool>,
+    early_withdraw_penalty: Option<Decimal>,
+    penalty_recipient: Option<FeeRecipient>,
+    lock_tiers: Option<Vec<LockTier>>,
+    auto_invest: Option<bool>,
+    auto_invest_threshold: Option<Uint128>,
+    migration_tolerance: Option<Decimal>,
+    profit_unlock_period: Option<u64>,
+    management_fee: Option<Decimal>,
+    deposit_fee: Option<Decimal>,
+    withdraw_fee: Option<Decimal>,
+    fee_recipient: Option<FeeRecipient>,
 ) -> StdResult<Response> {
     let mut config = read_config(deps.storage)?;
 
@@ -27,10 +54,6 @@ pub fn update_config(
         config.controller = deps.api.addr_canonicalize(&controller)?;
     }
 
-    if let Some(strategy) = strategy {
-        config.strategy = Some(deps.api.addr_canonicalize(&strategy)?);
-    }
-
     if let Some(invest_percentage) = invest_percentage {
         if invest_percentage > Decimal::one() {
             return Err(StdError::generic_err(
@@ -49,13 +72,155 @@ pub fn update_config(
         config.force_withdraw = force_withdraw;
     }
 
+    if let Some(early_withdraw) = early_withdraw {
+        config.early_withdraw = early_withdraw;
+    }
+
+    if let Some(early_withdraw_penalty) = early_withdraw_penalty {
+        if early_withdraw_penalty > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Early withdraw penalty must be less than 100%",
+            ));
+        }
+
+        config.early_withdraw_penalty = early_withdraw_penalty;
+    }
+
+    if let Some(penalty_recipient) = penalty_recipient {
+        config.penalty_recipient = penalty_recipient;
+    }
+
+    if let Some(lock_tiers) = lock_tiers {
+        if lock_tiers.iter().any(|tier| tier.boost < Decimal::one()) {
+            return Err(StdError::generic_err("Lock tier boost must be at least 1"));
+        }
+
+        config.lock_tiers = lock_tiers;
+    }
+
+    if let Some(auto_invest) = auto_invest {
+        config.auto_invest = auto_invest;
+    }
+
+    if let Some(auto_invest_threshold) = auto_invest_threshold {
+        config.auto_invest_threshold = auto_invest_threshold;
+    }
+
+    if let Some(migration_tolerance) = migration_tolerance {
+        if migration_tolerance > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Migration tolerance must be less than 100%",
+            ));
+        }
+
+        config.migration_tolerance = migration_tolerance;
+    }
+
+    if let Some(profit_unlock_period) = profit_unlock_period {
+        config.profit_unlock_period = profit_unlock_period;
+    }
+
+    if let Some(management_fee) = management_fee {
+        if management_fee > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Management fee must be less than 100%",
+            ));
+        }
+
+        config.management_fee = management_fee;
+    }
+
+    if let Some(deposit_fee) = deposit_fee {
+        if deposit_fee > Decimal::one() {
+            return Err(StdError::generic_err("Deposit fee must be less than 100%"));
+        }
+
+        config.deposit_fee = deposit_fee;
+    }
+
+    if let Some(withdraw_fee) = withdraw_fee {
+        if withdraw_fee > Decimal::one() {
+            return Err(StdError::generic_err("Withdraw fee must be less than 100%"));
+        }
+
+        config.withdraw_fee = withdraw_fee;
+    }
+
+    if let Some(fee_recipient) = fee_recipient {
+        config.fee_recipient = fee_recipient;
+    }
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
-/// Send available amount to strategy
-pub fn invest(deps: Deps, info: MessageInfo) -> StdResult<Response> {
+/// Replace strategies
+pub fn update_strategies(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    strategies: Vec<StrategyInfo>,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_governance_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        &info.sender.to_string(),
+    )?;
+
+    let old_strategies = read_strategies(deps.storage)?;
+
+    let mut new_strategies: Vec<StrategyInfoRaw> = vec![];
+    let mut total_weight = Decimal::zero();
+    for strategy in strategies {
+        let addr = deps.api.addr_canonicalize(&strategy.addr)?;
+        if new_strategies.iter().any(|info| info.addr == addr) {
+            return Err(StdError::generic_err("Duplicate strategy"));
+        }
+
+        // Debt accounting is kept for existing strategies
+        let mut new_strategy = match old_strategies.iter().find(|info| info.addr == addr) {
+            Some(old_strategy) => old_strategy.clone(),
+            None => StrategyInfoRaw::new(addr, env.block.time.seconds()),
+        };
+        new_strategy.weight = strategy.weight;
+        new_strategy.debt_limit = strategy.debt_limit;
+
+        total_weight = total_weight + strategy.weight;
+        new_strategies.push(new_strategy);
+    }
+
+    if total_weight > Decimal::one() {
+        return Err(StdError::generic_err(
+            "Total strategy weight must be less than 100%",
+        ));
+    }
+
+    for old_strategy in old_strategies {
+        if new_strategies.iter().any(|info| info.addr == old_strategy.addr) {
+            continue;
+        }
+
+        let invested_balance = query_strategy_total_balance(
+            deps.as_ref(),
+            deps.api.addr_humanize(&old_strategy.addr)?.to_string(),
+        )?;
+        if !invested_balance.is_zero() {
+            return Err(StdError::generic_err(
+                "Strategy holds funds, use MigrateStrategy",
+            ));
+        }
+    }
+
+    store_strategies(deps.storage, &new_strategies)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_strategies")]))
+}
+
+/// Send available amount to strategies
+pub fn invest(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
     assert_access_privilege(
@@ -64,29 +229,288 @@ pub fn invest(deps: Deps, info: MessageInfo) -> StdResult<Response> {
         info.sender,
     )?;
 
-    if config.strategy.is_none() {
+    if read_strategies(deps.storage)?.is_empty() {
         return Err(StdError::generic_err("Strategy is not defined"));
     }
 
-    let available_balance = get_available(deps, config.clone())?;
+    let (available_balance, messages) = get_invest_messages(deps, config, false)?;
     if available_balance.is_zero() {
         return Err(StdError::generic_err("Nothing to invest"));
     }
 
-    let asset = Asset {
-        amount: available_balance,
-        info: AssetInfo::NativeToken {
-            denom: config.stable_denom,
-        },
-    };
-
     Ok(Response::new()
-        .add_messages(vec![asset.into_msg(
-            &deps.querier,
-            deps.api.addr_humanize(&config.strategy.unwrap())?,
-        )?])
+        .add_messages(messages)
         .add_attributes(vec![
             attr("action", "invest"),
             attr("amount", available_balance),
         ]))
 }
+
+/// Withdraw all funds from current strategy to vault,
+/// and execute hook to switch to new strategy
+pub fn migrate_strategy(
+    deps: Deps,
+    env: Env,
+    info: MessageInfo,
+    strategy: String,
+    new_strategy: String,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_governance_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        &info.sender.to_string(),
+    )?;
+
+    let new_strategy_raw = deps.api.addr_canonicalize(&new_strategy)?;
+    let strategy_raw = deps.api.addr_canonicalize(&strategy)?;
+    let strategies = read_strategies(deps.storage)?;
+    if !strategies.iter().any(|info| info.addr == strategy_raw) {
+        return Err(StdError::generic_err("Strategy not found"));
+    }
+    if strategies.iter().any(|info| info.addr == new_strategy_raw) {
+        return Err(StdError::generic_err("Duplicate strategy"));
+    }
+
+    let old_strategy = strategy;
+
+    let expected_amount = query_strategy_total_balance(deps, old_strategy.clone())?;
+    let previous_balance = get_vault_balance(deps, config)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    if !expected_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: old_strategy.clone(),
+            msg: to_binary(&StrategyExecuteMsg::WithdrawAll {})?,
+            funds: vec![],
+        }));
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: old_strategy.clone(),
+            msg: to_binary(&StrategyExecuteMsg::WithdrawToVault { amount: None })?,
+            funds: vec![],
+        }));
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::MigrateStrategyHook {
+            strategy: old_strategy.clone(),
+            new_strategy: new_strategy.clone(),
+            expected_amount,
+            previous_balance,
+        })?,
+        funds: vec![],
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "migrate_strategy"),
+        attr("old_strategy", old_strategy),
+        attr("new_strategy", new_strategy),
+        attr("expected_amount", expected_amount),
+    ]))
+}
+
+/// Check returned amount of old strategy and switch to new strategy
+pub fn migrate_strategy_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    strategy: String,
+    new_strategy: String,
+    expected_amount: Uint128,
+    previous_balance: Uint128,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let returned_amount =
+        get_vault_balance(deps.as_ref(), config.clone())?.saturating_sub(previous_balance);
+    let min_amount = expected_amount * (Decimal::one() - config.migration_tolerance);
+
+    if returned_amount < min_amount {
+        return Err(StdError::generic_err(format!(
+            "Migration returned {} which is less than {}",
+            returned_amount, min_amount
+        )));
+    }
+
+    let strategy_raw = deps.api.addr_canonicalize(&strategy)?;
+    let mut strategies = read_strategies(deps.storage)?;
+    let strategy_info = match strategies.iter_mut().find(|info| info.addr == strategy_raw) {
+        Some(strategy_info) => strategy_info,
+        None => return Err(StdError::generic_err("Strategy not found")),
+    };
+    *strategy_info = StrategyInfoRaw {
+        weight: strategy_info.weight,
+        debt_limit: strategy_info.debt_limit,
+        ..StrategyInfoRaw::new(
+            deps.api.addr_canonicalize(&new_strategy)?,
+            env.block.time.seconds(),
+        )
+    };
+    store_strategies(deps.storage, &strategies)?;
+
+    let mut retired_strategies = read_retired_strategies(deps.storage)?;
+    retired_strategies.push(RetiredStrategy {
+        strategy: strategy_raw,
+        retired_at: env.block.time.seconds(),
+        expected_amount,
+        returned_amount,
+    });
+    store_retired_strategies(deps.storage, &retired_strategies)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate_strategy_hook"),
+        attr("new_strategy", new_strategy),
+        attr("returned_amount", returned_amount),
+    ]))
+}
+
+/// Send available amount to strategies, and deploy it if auto invest is enabled
+/// Auto invest is skipped below the auto invest threshold
+pub fn get_auto_invest_messages(
+    deps: DepsMut,
+    config: Config,
+) -> StdResult<(Uint128, Vec<CosmosMsg>)> {
+    if !config.auto_invest {
+        return Ok((Uint128::zero(), vec![]));
+    }
+
+    let available_balance = get_available(deps.as_ref(), config.clone())?;
+    if available_balance.is_zero() || available_balance < config.auto_invest_threshold {
+        return Ok((Uint128::zero(), vec![]));
+    }
+
+    get_invest_messages(deps, config, true)
+}
+
+/// Build messages sending allocations to strategies, and add them to strategy debt
+fn get_invest_messages(
+    deps: DepsMut,
+    config: Config,
+    deploy: bool,
+) -> StdResult<(Uint128, Vec<CosmosMsg>)> {
+    let mut total_amount = Uint128::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    let allocations = get_invest_allocations(deps.as_ref(), config.clone())?;
+    let mut strategies = read_strategies(deps.storage)?;
+    for (strategy, amount) in allocations {
+        if amount.is_zero() {
+            continue;
+        }
+
+        let strategy_raw = deps.api.addr_canonicalize(strategy.as_str())?;
+        if let Some(strategy_info) = strategies.iter_mut().find(|info| info.addr == strategy_raw) {
+            strategy_info.total_debt += amount;
+        }
+
+        let asset = Asset {
+            amount,
+            info: AssetInfo::NativeToken {
+                denom: config.stable_denom.clone(),
+            },
+        };
+
+        total_amount += amount;
+        messages.push(asset.into_msg(&deps.querier, strategy.clone())?);
+        if deploy {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: strategy.to_string(),
+                msg: to_binary(&StrategyExecuteMsg::Deploy {})?,
+                funds: vec![],
+            }));
+        }
+    }
+    store_strategies(deps.storage, &strategies)?;
+
+    Ok((total_amount, messages))
+}
+
+/// Only registered or retired strategy can execute
+/// Record harvest result of the strategy and lock the gain
+pub fn report(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    gain: Uint128,
+    loss: Uint128,
+    debt_payment: Uint128,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    let received: Uint128 = info
+        .funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(Uint128::zero);
+    if received != debt_payment {
+        return Err(StdError::generic_err("Debt payment must match sent funds"));
+    }
+
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut strategies = read_strategies(deps.storage)?;
+    let strategy_info = match strategies.iter_mut().find(|info| info.addr == sender_raw) {
+        Some(strategy_info) => strategy_info,
+        None => return report_retired(deps, info, sender_raw, debt_payment),
+    };
+
+    let now = env.block.time.seconds();
+
+    strategy_info.total_debt = strategy_info
+        .total_debt
+        .saturating_sub(debt_payment + loss);
+    strategy_info.total_gain += gain;
+    strategy_info.total_loss += loss;
+    strategy_info.last_report = now;
+    store_strategies(deps.storage, &strategies)?;
+
+    let mut state = read_state(deps.storage)?;
+    accrue_management_fee(deps.as_ref(), &config, &mut state, now, Uint128::zero())?;
+    let messages = pay_management_fee(deps.as_ref(), &config, &mut state, Uint128::zero())?;
+
+    state.locked_profit = (get_locked_profit(&config, &state, now) + gain).saturating_sub(loss);
+    state.last_report = now;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "report"),
+        attr("strategy", info.sender),
+        attr("gain", gain),
+        attr("loss", loss),
+        attr("debt_payment", debt_payment),
+        attr("locked_profit", state.locked_profit),
+    ]))
+}
+
+/// Retired strategy has no debt left, only record the returned funds
+fn report_retired(
+    deps: DepsMut,
+    info: MessageInfo,
+    sender_raw: CanonicalAddr,
+    debt_payment: Uint128,
+) -> StdResult<Response> {
+    let mut retired_strategies = read_retired_strategies(deps.storage)?;
+    let retired = match retired_strategies
+        .iter_mut()
+        .rev()
+        .find(|retired| retired.strategy == sender_raw)
+    {
+        Some(retired) => retired,
+        None => return Err(StdError::generic_err("unauthorized")),
+    };
+    retired.returned_amount += debt_payment;
+    let returned_amount = retired.returned_amount;
+    store_retired_strategies(deps.storage, &retired_strategies)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "report_retired"),
+        attr("strategy", info.sender),
+        attr("debt_payment", debt_payment),
+        attr("returned_amount", returned_amount),
+    ]))
+}
@@ -0,0 +1,149 @@
+use cosmwasm_std::{attr, Decimal, DepsMut, Env, Response, StdResult, Uint128};
+
+use crate::querier::query_strategy_total_balance;
+use crate::state::{
+    read_config, read_deposit_info, read_legacy_config, read_legacy_deposit_infos,
+    read_legacy_state, read_state, store_config, store_deposit_info, store_state,
+    store_strategies, Config, DepositInfo, StrategyInfoRaw,
+};
+use athena::vault::{FeeRecipient, State};
+
+const DEFAULT_MIGRATE_LIMIT: u32 = 30;
+const MAX_MIGRATE_LIMIT: u32 = 100;
+
+/// Migrate the storage of the single strategy vault
+/// Deposits are migrated by pages, continued with start_after,
+/// and the principal totals are accumulated from the migrated deposits.
+/// Migration of already migrated storage without start_after does nothing
+pub fn migrate_legacy_storage(
+    mut deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Response> {
+    let (config, mut state) = match read_config(deps.storage) {
+        Ok(config) => {
+            if start_after.is_none() {
+                return Ok(Response::default());
+            }
+            (config, read_state(deps.storage)?)
+        }
+        Err(_) => migrate_legacy_config(deps.branch(), &env)?,
+    };
+
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_MIGRATE_LIMIT)
+        .min(MAX_MIGRATE_LIMIT) as usize;
+
+    // Deposits stored after the first migration are already in the new format
+    let legacy_deposit_infos = read_legacy_deposit_infos(deps.storage, start_after, limit)?;
+    let mut migrated = 0u64;
+    for (addr, legacy_deposit_info) in legacy_deposit_infos.iter() {
+        if read_deposit_info(deps.storage, addr).is_ok() {
+            continue;
+        }
+
+        let principal = legacy_deposit_info
+            .principal
+            .saturating_sub(legacy_deposit_info.principal_claimed);
+        state.total_principal += principal;
+        state.total_boosted_principal += principal;
+
+        store_deposit_info(
+            deps.storage,
+            addr,
+            &DepositInfo {
+                principal: legacy_deposit_info.principal,
+                current_amount: legacy_deposit_info.current_amount,
+                share: legacy_deposit_info.share,
+                boost: Decimal::one(),
+                lock_period: config.lock_period,
+                maturity: legacy_deposit_info.maturity,
+                yield_amount: legacy_deposit_info.yield_amount,
+                yield_claimed: legacy_deposit_info.yield_claimed,
+                principal_claimed: legacy_deposit_info.principal_claimed,
+                principal_index: Decimal::one(),
+            },
+        )?;
+        migrated += 1;
+    }
+    store_state(deps.storage, &state)?;
+
+    let last_deposit = legacy_deposit_infos
+        .last()
+        .map(|(addr, _)| addr.to_string())
+        .unwrap_or_default();
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("deposits", migrated.to_string()),
+        attr("last_deposit", last_deposit),
+        attr("total_principal", state.total_principal),
+    ]))
+}
+
+/// Migrate the config and state of the single strategy vault,
+/// the strategy is registered with full weight and its balance as debt
+fn migrate_legacy_config(deps: DepsMut, env: &Env) -> StdResult<(Config, State)> {
+    let legacy_config = read_legacy_config(deps.storage)?;
+    let legacy_state = read_legacy_state(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let config = Config {
+        contract_addr: legacy_config.contract_addr,
+        controller: legacy_config.controller,
+        stable_denom: legacy_config.stable_denom,
+        invest_percentage: legacy_config.invest_percentage,
+        lock_period: legacy_config.lock_period,
+        force_withdraw: legacy_config.force_withdraw,
+        early_withdraw: false,
+        early_withdraw_penalty: Decimal::zero(),
+        penalty_recipient: FeeRecipient::Depositors,
+        lock_tiers: vec![],
+        auto_invest: false,
+        auto_invest_threshold: Uint128::zero(),
+        migration_tolerance: Decimal::percent(1u64),
+        profit_unlock_period: 21600u64,
+        management_fee: Decimal::zero(),
+        deposit_fee: Decimal::zero(),
+        withdraw_fee: Decimal::zero(),
+        fee_recipient: FeeRecipient::Treasury,
+    };
+    store_config(deps.storage, &config)?;
+
+    let state = State {
+        total_share: legacy_state.total_share,
+        total_boosted_share: legacy_state.total_share,
+        total_principal: Uint128::zero(),
+        total_boosted_principal: Uint128::zero(),
+        total_subsidized: legacy_state.total_subsidized,
+        insurance_reserve: Uint128::zero(),
+        principal_index: Decimal::one(),
+        locked_profit: Uint128::zero(),
+        last_report: now,
+        management_fee_payable: Uint128::zero(),
+        management_fee_paid: Uint128::zero(),
+        last_fee_accrual: now,
+    };
+
+    if let Some(strategy) = legacy_config.strategy {
+        let total_debt = query_strategy_total_balance(
+            deps.as_ref(),
+            deps.api.addr_humanize(&strategy)?.to_string(),
+        )?;
+        store_strategies(
+            deps.storage,
+            &[StrategyInfoRaw {
+                weight: Decimal::one(),
+                debt_limit: Uint128::MAX,
+                total_debt,
+                ..StrategyInfoRaw::new(strategy, now)
+            }],
+        )?;
+    }
+
+    Ok((config, state))
+}
@@ -1,29 +1,43 @@
-use cosmwasm_std::{to_binary, Decimal, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
+use cosmwasm_std::{
+    to_binary, Addr, Decimal, Deps, Env, QueryRequest, StdError, StdResult, Uint128, WasmQuery,
+};
+use std::cmp::min;
 
-use crate::state::{read_config, read_deposit_info, read_state, Config, DepositInfo};
+use crate::fee::{accrue_management_fee, compute_early_withdraw_penalty_rate};
+use crate::insurance::apply_principal_index;
+use crate::state::{
+    read_config, read_deposit_info, read_retired_strategies, read_state, read_strategies, Config,
+    DepositInfo,
+};
 use athena::vault_strategy::QueryMsg as StrategyQueryMsg;
-use athena::vault::{ConfigResponse, DepositInfoResponse, State};
+use athena::vault::{
+    ConfigResponse, DepositInfoResponse, EarlyWithdrawPenaltyResponse, FeesResponse,
+    LockTiersResponse, PreviewDepositResponse, PreviewWithdrawResponse,
+    RetiredStrategiesResponse, RetiredStrategyResponse, State, StrategiesResponse,
+    StrategyCreditResponse, StrategyDebtResponse, StrategyInfo,
+};
 use athena::asset::AssetInfo;
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = read_config(deps.storage)?;
-    let strategy = if config.strategy.is_none() {
-        None
-    } else {
-        Some(
-            deps.api
-                .addr_humanize(&config.strategy.unwrap())?
-                .to_string(),
-        )
-    };
     let resp = ConfigResponse {
         contract_addr: deps.api.addr_humanize(&config.contract_addr)?.to_string(),
         controller: deps.api.addr_humanize(&config.controller)?.to_string(),
         stable_denom: config.stable_denom,
-        strategy,
         invest_percentage: config.invest_percentage,
         lock_period: config.lock_period,
         force_withdraw: config.force_withdraw,
+        early_withdraw: config.early_withdraw,
+        early_withdraw_penalty: config.early_withdraw_penalty,
+        penalty_recipient: config.penalty_recipient,
+        auto_invest: config.auto_invest,
+        auto_invest_threshold: config.auto_invest_threshold,
+        migration_tolerance: config.migration_tolerance,
+        profit_unlock_period: config.profit_unlock_period,
+        management_fee: config.management_fee,
+        deposit_fee: config.deposit_fee,
+        withdraw_fee: config.withdraw_fee,
+        fee_recipient: config.fee_recipient,
     };
 
     Ok(resp)
@@ -51,21 +65,34 @@ pub fn query_total_balance(deps: Deps) -> StdResult<Uint128> {
     Ok(get_total_balance(deps, config)?)
 }
 
+pub fn query_strategy_total_balance(deps: Deps, strategy: String) -> StdResult<Uint128> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: strategy,
+        msg: to_binary(&StrategyQueryMsg::TotalBalance {})?,
+    }))
+}
+
+/// Return the strategy balance net of the performance fee accrued on its unrealized gain
+pub fn query_strategy_net_total_balance(deps: Deps, strategy: String) -> StdResult<Uint128> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: strategy,
+        msg: to_binary(&StrategyQueryMsg::NetTotalBalance {})?,
+    }))
+}
+
+/// Return the balance owned by the vault, which prices the shares.
+/// Strategies are valued net of the performance fee they take on redemption
 pub fn get_total_balance(deps: Deps, config: Config) -> StdResult<Uint128> {
-    if config.strategy.is_none() {
-        Ok(get_vault_balance(deps, config.clone())?)
-    } else {
-        let invested_balance = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps
-                .api
-                .addr_humanize(&config.clone().strategy.unwrap())?
-                .to_string(),
-            msg: to_binary(&StrategyQueryMsg::TotalBalance {})?,
-        }))?;
-        Ok(get_vault_balance(deps, config.clone())?
-            .checked_add(invested_balance)
-            .unwrap())
+    let mut total_balance = get_vault_balance(deps, config)?;
+
+    for strategy in read_strategies(deps.storage)? {
+        total_balance += query_strategy_net_total_balance(
+            deps,
+            deps.api.addr_humanize(&strategy.addr)?.to_string(),
+        )?;
     }
+
+    Ok(total_balance)
 }
 
 pub fn query_available(deps: Deps) -> StdResult<Uint128> {
@@ -74,62 +101,165 @@ pub fn query_available(deps: Deps) -> StdResult<Uint128> {
 }
 
 pub fn get_available(deps: Deps, config: Config) -> StdResult<Uint128> {
-    if config.clone().strategy.is_none() {
-        Ok(Uint128::zero())
+    Ok(get_invest_allocations(deps, config)?
+        .iter()
+        .fold(Uint128::zero(), |total, (_, amount)| total + *amount))
+}
+
+/// Return amount to invest to each strategy.
+/// Invested balance targets invest percentage of total balance,
+/// and is split among strategies by weight capped by debt limit
+pub fn get_invest_allocations(deps: Deps, config: Config) -> StdResult<Vec<(Addr, Uint128)>> {
+    let strategies = read_strategies(deps.storage)?;
+    let vault_balance = get_vault_balance(deps, config.clone())?;
+
+    let mut invested_balance = Uint128::zero();
+    let mut strategy_balances: Vec<(Addr, Uint128)> = vec![];
+    for strategy in strategies.iter() {
+        let addr = deps.api.addr_humanize(&strategy.addr)?;
+        let balance = query_strategy_net_total_balance(deps, addr.to_string())?;
+        invested_balance += balance;
+        strategy_balances.push((addr, balance));
+    }
+
+    // Insurance reserve and payable management fee are kept in the vault
+    let state = read_state(deps.storage)?;
+    let target_balance = vault_balance
+        .checked_add(invested_balance)?
+        .saturating_sub(state.insurance_reserve + state.management_fee_payable)
+        * config.invest_percentage;
+    let mut available = target_balance.saturating_sub(invested_balance);
+
+    let mut allocations: Vec<(Addr, Uint128)> = vec![];
+    for (strategy, (addr, balance)) in strategies.iter().zip(strategy_balances) {
+        let strategy_target = min(target_balance * strategy.weight, strategy.debt_limit);
+        let amount = min(strategy_target.saturating_sub(balance), available);
+        available = available.checked_sub(amount)?;
+        allocations.push((addr, amount));
+    }
+
+    Ok(allocations)
+}
+
+/// Return the gain of the last reports which is not released to shares yet
+pub fn get_locked_profit(config: &Config, state: &State, time: u64) -> Uint128 {
+    let elapsed = time.saturating_sub(state.last_report);
+    if elapsed >= config.profit_unlock_period {
+        Uint128::zero()
     } else {
-        let vault_balance = get_vault_balance(deps, config.clone())?;
-        let invested_balance = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps
-                .api
-                .addr_humanize(&config.clone().strategy.unwrap())?
-                .to_string(),
-            msg: to_binary(&StrategyQueryMsg::TotalBalance {})?,
-        }))?;
-        let max_available_balance =
-            vault_balance.checked_add(invested_balance)? * config.invest_percentage;
-        if max_available_balance > invested_balance {
-            Ok(max_available_balance.checked_sub(invested_balance).unwrap())
-        } else {
-            Ok(Uint128::zero())
-        }
+        state.locked_profit.multiply_ratio(
+            config.profit_unlock_period - elapsed,
+            config.profit_unlock_period,
+        )
     }
 }
 
+/// Return the balance not owned by shares
+pub fn get_excluded_balance(config: &Config, state: &State, time: u64) -> Uint128 {
+    state.total_subsidized
+        + state.insurance_reserve
+        + state.management_fee_payable
+        + get_locked_profit(config, state, time)
+}
+
+/// Return the balance of the share.
+/// The yield of the deposit over its principal is weighted by its lock boost,
+/// and scaled so that the weighted yields of all deposits add up to the yield of the pool.
+/// A deposit under its principal takes the loss weighted the same way
 pub fn get_balance_by_share(
     deps: Deps,
     config: Config,
     state: State,
     share: Uint128,
+    boost: Decimal,
+    principal: Uint128,
+    time: u64,
 ) -> StdResult<Uint128> {
+    let excluded_balance = get_excluded_balance(&config, &state, time);
     let total_balance = get_total_balance(deps, config)?;
 
-    if state.total_share.is_zero() || total_balance < state.total_subsidized {
-        Ok(Uint128::zero())
-    } else if share >= state.total_share {
-        Ok(total_balance.checked_sub(state.total_subsidized)?)
+    if state.total_share.is_zero() || total_balance < excluded_balance {
+        return Ok(Uint128::zero());
+    }
+
+    let pool_balance = total_balance.checked_sub(excluded_balance)?;
+    let amount = if share >= state.total_share {
+        pool_balance
+    } else {
+        pool_balance * Decimal::from_ratio(share, state.total_share)
+    };
+
+    if state.total_boosted_share == state.total_share {
+        return Ok(amount);
+    }
+
+    // Yield of the pool, and the sum of yields of all deposits weighted by boost
+    let pool_yield = pool_balance.saturating_sub(state.total_principal);
+    let boosted_yield = pool_balance
+        .multiply_ratio(state.total_boosted_share, state.total_share)
+        .saturating_sub(state.total_boosted_principal);
+    if pool_yield.is_zero() || boosted_yield.is_zero() {
+        return Ok(amount);
+    }
+
+    if amount >= principal {
+        Ok(principal
+            + (amount.checked_sub(principal)? * boost).multiply_ratio(pool_yield, boosted_yield))
+    } else {
+        // Rounded against the deposit, so that the pool is never overdrawn
+        let loss = (principal.checked_sub(amount)? * boost)
+            .multiply_ratio(pool_yield, boosted_yield)
+            + Uint128::from(1u128);
+        Ok(principal.saturating_sub(loss))
+    }
+}
+
+/// Return the share issued for the amount.
+/// `inflow` is the amount already received by the vault in the current transaction
+pub fn get_share_by_amount(
+    deps: Deps,
+    config: Config,
+    state: &State,
+    amount: Uint128,
+    inflow: Uint128,
+    time: u64,
+) -> StdResult<Uint128> {
+    let excluded_balance = get_excluded_balance(&config, state, time);
+    let total_balance = get_total_balance(deps, config)?.checked_sub(inflow)?;
+
+    if state.total_share.is_zero() || total_balance <= excluded_balance {
+        Ok(amount)
     } else {
-        Ok(total_balance.checked_sub(state.total_subsidized)?
-            * Decimal::from_ratio(share, state.total_share))
+        Ok(state.total_share
+            * Decimal::from_ratio(amount, total_balance.checked_sub(excluded_balance)?))
     }
 }
 
 pub fn query_deposit_info(deps: Deps, addr: String) -> StdResult<DepositInfoResponse> {
-    let deposit_info = match read_deposit_info(deps.storage, &deps.api.addr_validate(&addr)?) {
+    let state = read_state(deps.storage)?;
+    let mut deposit_info = match read_deposit_info(deps.storage, &deps.api.addr_validate(&addr)?) {
         Ok(info) => info,
         Err(_) => DepositInfo{
             principal: Uint128::zero(),
             current_amount: Uint128::zero(),
             share: Uint128::zero(),
+            boost: Decimal::one(),
+            lock_period: u64::MIN,
             maturity: u64::MIN,
             yield_amount: Uint128::zero(),
             yield_claimed: Uint128::zero(),
             principal_claimed: Uint128::zero(),
+            principal_index: state.principal_index,
         }
     };
+    apply_principal_index(&state, &mut deposit_info)?;
+
     Ok(DepositInfoResponse {
         principal: deposit_info.principal,
         current_amount: deposit_info.current_amount,
         share: deposit_info.share,
+        boost: deposit_info.boost,
+        lock_period: deposit_info.lock_period,
         maturity: deposit_info.maturity,
         yield_amount: deposit_info.yield_amount,
         yield_claimed: deposit_info.yield_claimed,
@@ -137,7 +267,202 @@ pub fn query_deposit_info(deps: Deps, addr: String) -> StdResult<DepositInfoResp
     })
 }
 
+pub fn query_lock_tiers(deps: Deps) -> StdResult<LockTiersResponse> {
+    let config = read_config(deps.storage)?;
+    Ok(LockTiersResponse {
+        lock_tiers: config.lock_tiers,
+    })
+}
+
+pub fn query_strategies(deps: Deps) -> StdResult<StrategiesResponse> {
+    let strategies = read_strategies(deps.storage)?;
+
+    Ok(StrategiesResponse {
+        strategies: strategies
+            .into_iter()
+            .map(|strategy| {
+                Ok(StrategyInfo {
+                    addr: deps.api.addr_humanize(&strategy.addr)?.to_string(),
+                    weight: strategy.weight,
+                    debt_limit: strategy.debt_limit,
+                })
+            })
+            .collect::<StdResult<Vec<StrategyInfo>>>()?,
+    })
+}
+
+pub fn query_strategy_debt(deps: Deps, strategy: String) -> StdResult<StrategyDebtResponse> {
+    let strategy_raw = deps.api.addr_canonicalize(&strategy)?;
+
+    let strategy_info = read_strategies(deps.storage)?
+        .into_iter()
+        .find(|info| info.addr == strategy_raw)
+        .ok_or_else(|| StdError::generic_err("Strategy not found"))?;
+
+    Ok(StrategyDebtResponse {
+        strategy,
+        total_debt: strategy_info.total_debt,
+        total_gain: strategy_info.total_gain,
+        total_loss: strategy_info.total_loss,
+        last_report: strategy_info.last_report,
+        debt_limit: strategy_info.debt_limit,
+    })
+}
+
+pub fn query_strategy_credit(deps: Deps, strategy: String) -> StdResult<StrategyCreditResponse> {
+    let config = read_config(deps.storage)?;
+    let strategy_addr = deps.api.addr_validate(&strategy)?;
+
+    let credit_available = get_invest_allocations(deps, config)?
+        .into_iter()
+        .find(|(addr, _)| *addr == strategy_addr)
+        .map(|(_, amount)| amount)
+        .ok_or_else(|| StdError::generic_err("Strategy not found"))?;
+
+    Ok(StrategyCreditResponse {
+        balance: query_strategy_total_balance(deps, strategy.clone())?,
+        strategy,
+        credit_available,
+    })
+}
+
+pub fn query_retired_strategies(deps: Deps) -> StdResult<RetiredStrategiesResponse> {
+    let retired_strategies = read_retired_strategies(deps.storage)?;
+
+    Ok(RetiredStrategiesResponse {
+        strategies: retired_strategies
+            .into_iter()
+            .map(|retired| {
+                Ok(RetiredStrategyResponse {
+                    strategy: deps.api.addr_humanize(&retired.strategy)?.to_string(),
+                    retired_at: retired.retired_at,
+                    expected_amount: retired.expected_amount,
+                    returned_amount: retired.returned_amount,
+                })
+            })
+            .collect::<StdResult<Vec<RetiredStrategyResponse>>>()?,
+    })
+}
+
+pub fn query_fees(deps: Deps, env: Env) -> StdResult<FeesResponse> {
+    let config = read_config(deps.storage)?;
+    let mut state = read_state(deps.storage)?;
+    accrue_management_fee(
+        deps,
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
+
+    Ok(FeesResponse {
+        management_fee: config.management_fee,
+        management_fee_payable: state.management_fee_payable,
+        management_fee_paid: state.management_fee_paid,
+        last_fee_accrual: state.last_fee_accrual,
+    })
+}
+
+pub fn query_preview_deposit(
+    deps: Deps,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<PreviewDepositResponse> {
+    let config = read_config(deps.storage)?;
+    let mut state = read_state(deps.storage)?;
+    accrue_management_fee(
+        deps,
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        Uint128::zero(),
+    )?;
+
+    let deposit_fee = amount * config.deposit_fee;
+    let principal = amount.checked_sub(deposit_fee)?;
+    let share = get_share_by_amount(
+        deps,
+        config,
+        &state,
+        principal,
+        Uint128::zero(),
+        env.block.time.seconds(),
+    )?;
+
+    Ok(PreviewDepositResponse {
+        deposit_fee,
+        principal,
+        share,
+    })
+}
+
+pub fn query_preview_withdraw(
+    deps: Deps,
+    env: Env,
+    addr: String,
+    amount: Uint128,
+) -> StdResult<PreviewWithdrawResponse> {
+    let config = read_config(deps.storage)?;
+
+    let penalty_rate = match read_deposit_info(deps.storage, &deps.api.addr_validate(&addr)?) {
+        Ok(deposit_info) => {
+            compute_early_withdraw_penalty_rate(&config, &deposit_info, env.block.time.seconds())
+        }
+        Err(_) => Decimal::zero(),
+    };
+
+    let penalty = amount * penalty_rate;
+    let withdraw_fee = amount.checked_sub(penalty)? * config.withdraw_fee;
+
+    Ok(PreviewWithdrawResponse {
+        withdraw_fee,
+        penalty,
+        return_amount: amount.checked_sub(penalty + withdraw_fee)?,
+    })
+}
+
 pub fn query_state(deps: Deps) -> StdResult<State> {
     let state = read_state(deps.storage)?;
     Ok(state)
 }
+
+pub fn query_early_withdraw_penalty(
+    deps: Deps,
+    env: Env,
+    addr: String,
+) -> StdResult<EarlyWithdrawPenaltyResponse> {
+    let config = read_config(deps.storage)?;
+    let state = read_state(deps.storage)?;
+
+    let mut deposit_info = match read_deposit_info(deps.storage, &deps.api.addr_validate(&addr)?) {
+        Ok(info) => info,
+        Err(_) => {
+            return Ok(EarlyWithdrawPenaltyResponse {
+                maturity: u64::MIN,
+                penalty_rate: Decimal::zero(),
+                penalty_amount: Uint128::zero(),
+            })
+        }
+    };
+    apply_principal_index(&state, &mut deposit_info)?;
+
+    let penalty_rate =
+        compute_early_withdraw_penalty_rate(&config, &deposit_info, env.block.time.seconds());
+    let amount = get_balance_by_share(
+        deps,
+        config,
+        state,
+        deposit_info.share,
+        deposit_info.boost,
+        deposit_info
+            .principal
+            .checked_sub(deposit_info.principal_claimed)?,
+        env.block.time.seconds(),
+    )?;
+
+    Ok(EarlyWithdrawPenaltyResponse {
+        maturity: deposit_info.maturity,
+        penalty_rate,
+        penalty_amount: amount * penalty_rate,
+    })
+}
@@ -1,11 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{CanonicalAddr, Addr, Decimal, StdResult, Storage, Uint128};
+use cosmwasm_std::{CanonicalAddr, Addr, Decimal, Order, StdResult, Storage, Uint128};
 use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
-use athena::vault::State;
+use athena::vault::{FeeRecipient, LockTier, State};
 
 const KEY_CONFIG: &[u8] = b"config";
 const KEY_VAULT_STATE: &[u8] = b"state";
+const KEY_STRATEGIES: &[u8] = b"strategies";
+const KEY_RETIRED_STRATEGIES: &[u8] = b"retired_strategies";
 const PREFIX_KEY_DEPOSIT_INFO: &[u8] = b"deposit_info";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -13,10 +15,28 @@ pub struct Config {
     pub contract_addr: CanonicalAddr,
     pub controller: CanonicalAddr,
     pub stable_denom: String,
-    pub strategy: Option<CanonicalAddr>,
     pub invest_percentage: Decimal,
     pub lock_period: u64,
     pub force_withdraw: bool,
+    pub early_withdraw: bool,
+    pub early_withdraw_penalty: Decimal,
+    pub penalty_recipient: FeeRecipient,
+    pub lock_tiers: Vec<LockTier>,
+    /// Send available amount to strategy on deposit
+    pub auto_invest: bool,
+    /// Minimum available amount to auto invest
+    pub auto_invest_threshold: Uint128,
+    /// Allowed shortfall of the amount returned by strategy migration
+    pub migration_tolerance: Decimal,
+    /// Seconds over which reported gain is released to shares
+    pub profit_unlock_period: u64,
+    /// Annual fee rate on the share pool
+    pub management_fee: Decimal,
+    /// Fee rate on deposited amount
+    pub deposit_fee: Decimal,
+    /// Fee rate on withdrawn amount
+    pub withdraw_fee: Decimal,
+    pub fee_recipient: FeeRecipient,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -24,10 +44,47 @@ pub struct DepositInfo {
     pub principal: Uint128,
     pub current_amount: Uint128,
     pub share: Uint128,
+    pub boost: Decimal,
+    pub lock_period: u64,
     pub maturity: u64,
     pub yield_amount: Uint128,
     pub yield_claimed: Uint128,
     pub principal_claimed: Uint128,
+    pub principal_index: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyInfoRaw {
+    pub addr: CanonicalAddr,
+    pub weight: Decimal,
+    pub debt_limit: Uint128,
+    pub total_debt: Uint128,
+    pub total_gain: Uint128,
+    pub total_loss: Uint128,
+    pub last_report: u64,
+}
+
+impl StrategyInfoRaw {
+    /// Strategy without weight and debt
+    pub fn new(addr: CanonicalAddr, last_report: u64) -> Self {
+        StrategyInfoRaw {
+            addr,
+            weight: Decimal::zero(),
+            debt_limit: Uint128::zero(),
+            total_debt: Uint128::zero(),
+            total_gain: Uint128::zero(),
+            total_loss: Uint128::zero(),
+            last_report,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RetiredStrategy {
+    pub strategy: CanonicalAddr,
+    pub retired_at: u64,
+    pub expected_amount: Uint128,
+    pub returned_amount: Uint128,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -57,3 +114,86 @@ pub fn store_deposit_info(
 pub fn read_deposit_info(storage: &dyn Storage, addr: &Addr) -> StdResult<DepositInfo> {
     bucket_read(storage, PREFIX_KEY_DEPOSIT_INFO).load(addr.as_bytes())
 }
+
+pub fn store_strategies(
+    storage: &mut dyn Storage,
+    strategies: &[StrategyInfoRaw],
+) -> StdResult<()> {
+    singleton(storage, KEY_STRATEGIES).save(&strategies.to_vec())
+}
+
+pub fn read_strategies(storage: &dyn Storage) -> StdResult<Vec<StrategyInfoRaw>> {
+    Ok(singleton_read(storage, KEY_STRATEGIES)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_retired_strategies(
+    storage: &mut dyn Storage,
+    retired_strategies: &[RetiredStrategy],
+) -> StdResult<()> {
+    singleton(storage, KEY_RETIRED_STRATEGIES).save(&retired_strategies.to_vec())
+}
+
+pub fn read_retired_strategies(storage: &dyn Storage) -> StdResult<Vec<RetiredStrategy>> {
+    Ok(singleton_read(storage, KEY_RETIRED_STRATEGIES)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// Config of the single strategy vault, read by migration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub contract_addr: CanonicalAddr,
+    pub controller: CanonicalAddr,
+    pub stable_denom: String,
+    pub strategy: Option<CanonicalAddr>,
+    pub invest_percentage: Decimal,
+    pub lock_period: u64,
+    pub force_withdraw: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub total_share: Uint128,
+    pub total_subsidized: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyDepositInfo {
+    pub principal: Uint128,
+    pub current_amount: Uint128,
+    pub share: Uint128,
+    pub maturity: u64,
+    pub yield_amount: Uint128,
+    pub yield_claimed: Uint128,
+    pub principal_claimed: Uint128,
+}
+
+pub fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn read_legacy_state(storage: &dyn Storage) -> StdResult<LegacyState> {
+    singleton_read(storage, KEY_VAULT_STATE).load()
+}
+
+pub fn read_legacy_deposit_infos(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: usize,
+) -> StdResult<Vec<(Addr, LegacyDepositInfo)>> {
+    let start = start_after.map(|addr| {
+        let mut start = addr.as_bytes().to_vec();
+        start.push(0);
+        start
+    });
+    bucket_read(storage, PREFIX_KEY_DEPOSIT_INFO)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, deposit_info) = item?;
+            Ok((Addr::unchecked(String::from_utf8_lossy(&key)), deposit_info))
+        })
+        .collect()
+}
@@ -2,13 +2,17 @@
 mod tests {
 
     use crate::contract::{execute, instantiate, query};
+    use crate::querier::get_balance_by_share;
+    use crate::state::read_config;
     use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{
         from_binary, BankMsg, Coin, CosmosMsg, Decimal, Env, OwnedDeps, StdError,
         SubMsg, Uint128,
     };
-    use athena::vault::{ExecuteMsg, InstantiateMsg, QueryMsg, State};
+    use athena::vault::{
+        DepositInfoResponse, ExecuteMsg, FeeRecipient, InstantiateMsg, LockTier, QueryMsg, State,
+    };
 
     #[test]
     fn claim_principal() {
@@ -32,7 +36,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
         
         execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
 
@@ -82,7 +86,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
         
         execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
 
@@ -95,6 +99,196 @@ mod tests {
         assert_eq!(res, StdError::generic_err("Still locked"));
     }
 
+    #[test]
+    fn claim_principal_before_maturity_sends_penalty_to_treasury() {
+        let principal = Uint128::from(100000000u128);
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            force_withdraw: None,
+            lock_period: None,
+            early_withdraw: Some(true),
+            early_withdraw_penalty: Some(Decimal::percent(10u64)),
+            penalty_recipient: Some(FeeRecipient::Treasury),
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000020u128),
+            }],
+        )]);
+
+        let info = mock_info(
+            "addr",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: principal,
+            }],
+        );
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { lock_period: None }).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr", &[]),
+            ExecuteMsg::ClaimPrincipal {},
+        )
+        .unwrap();
+
+        let penalty = principal * Decimal::percent(10u64);
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("addr"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(principal - penalty).unwrap()
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("treasury"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(penalty).unwrap()
+                    }],
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn claim_principal_before_maturity_redistributes_penalty_to_depositors() {
+        let principal = Uint128::from(100000000u128);
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            force_withdraw: None,
+            lock_period: None,
+            early_withdraw: Some(true),
+            early_withdraw_penalty: Some(Decimal::percent(10u64)),
+            penalty_recipient: Some(FeeRecipient::Depositors),
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let set_balance = |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+                           amount: u128| {
+            deps.querier.with_balance(&[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(amount),
+                }],
+            )]);
+        };
+
+        set_balance(&mut deps, 100000000u128);
+        let info = mock_info("addr1", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { lock_period: None })
+            .unwrap();
+
+        set_balance(&mut deps, 200000000u128);
+        let info = mock_info("addr", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { lock_period: None })
+            .unwrap();
+
+        // 200 uusd of yield accrues before the early claim
+        set_balance(&mut deps, 200000200u128);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr", &[]),
+            ExecuteMsg::ClaimPrincipal {},
+        )
+        .unwrap();
+
+        let penalty = principal * Decimal::percent(10u64);
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps.querier.deduct_tax(principal - penalty).unwrap()
+                }],
+            }))]
+        );
+
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(state.locked_profit, penalty);
+
+        set_balance(&mut deps, 110000200u128);
+        let config = read_config(deps.as_ref().storage).unwrap();
+        let deposit_info: DepositInfoResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::DepositInfo {
+                    addr: String::from("addr1"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let balance_at = |time: u64| {
+            get_balance_by_share(
+                deps.as_ref(),
+                config.clone(),
+                state.clone(),
+                deposit_info.share,
+                deposit_info.boost,
+                deposit_info.principal - deposit_info.principal_claimed,
+                time,
+            )
+            .unwrap()
+        };
+
+        // Penalty is released to remaining shares over the profit unlock period,
+        // addr keeps the share of its unclaimed yield and takes a pro rata cut of it
+        let now = mock_env().block.time.seconds();
+        assert_eq!(balance_at(now), Uint128::from(100000099u128));
+        assert_eq!(
+            balance_at(now + config.profit_unlock_period),
+            Uint128::from(110000089u128)
+        );
+    }
+
     #[test]
     fn claim_yield() {
         let mut deps = mock_dependencies_with_querier(20, &[]);
@@ -117,7 +311,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
         
         execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
 
@@ -164,7 +358,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
         
         execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
 
@@ -203,7 +397,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
         
         execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
 
@@ -218,7 +412,17 @@ mod tests {
         assert_eq!(
             State {
                 total_share: principal,
+                total_boosted_share: principal,
+                total_principal: Uint128::from(100000000u128),
+                total_boosted_principal: Uint128::from(100000000u128),
                 total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
             },
             state
         );
@@ -250,14 +454,24 @@ mod tests {
         assert_eq!(
             State {
                 total_share: Uint128::zero(),
+                total_boosted_share: Uint128::zero(),
+                total_principal: Uint128::zero(),
+                total_boosted_principal: Uint128::zero(),
                 total_subsidized: principal_yield,
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: env.block.time.seconds(),
             },
             state
         );
 
         let claim_yield_msg = ExecuteMsg::ClaimYield {};
 
-        let claim_yield_res = execute(deps.as_mut(), env, claimer, claim_yield_msg).unwrap();
+        let claim_yield_res = execute(deps.as_mut(), env.clone(), claimer, claim_yield_msg).unwrap();
 
         assert_eq!(
             claim_yield_res.messages,
@@ -276,12 +490,235 @@ mod tests {
         assert_eq!(
             State {
                 total_share: Uint128::zero(),
+                total_boosted_share: Uint128::zero(),
+                total_principal: Uint128::zero(),
+                total_boosted_principal: Uint128::zero(),
                 total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: env.block.time.seconds(),
             },
             state
         );
     }
 
+    #[test]
+    fn claim_yield_is_weighted_by_lock_boost() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: Some(vec![LockTier {
+                lock_period: 600u64,
+                boost: Decimal::percent(200u64),
+            }]),
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let principal = Uint128::from(100000000u128);
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: principal,
+            }],
+        )]);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
+        let info = mock_info("addr", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+
+        execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: principal + principal,
+            }],
+        )]);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: Some(600u64) };
+        let info = mock_info("addr1", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+
+        execute(deps.as_mut(), mock_env(), info, deposit_msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+
+        assert_eq!(
+            State {
+                total_share: Uint128::from(200000000u128),
+                total_boosted_share: Uint128::from(300000000u128),
+                total_principal: Uint128::from(200000000u128),
+                total_boosted_principal: Uint128::from(300000000u128),
+                total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
+            },
+            state
+        );
+
+        // 60 UST of yield is split 1:2 between the two deposits
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(260000000u128),
+            }],
+        )]);
+
+        let mut env = mock_env();
+
+        add_block_by_seconds(&mut env, 620u64);
+
+        let res = execute(deps.as_mut(), env, mock_info("addr1", &[]), ExecuteMsg::ClaimYield {}).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr1"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps
+                        .querier
+                        .deduct_tax(Uint128::from(40000000u128))
+                        .unwrap()
+                }],
+            }))]
+        );
+    }
+
+    #[test]
+    fn boosted_balances_do_not_exceed_total_balance() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: Some(vec![LockTier {
+                lock_period: 600u64,
+                boost: Decimal::percent(200u64),
+            }]),
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let principal = Uint128::from(100000000u128);
+        let set_balance = |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+                           amount: u128| {
+            deps.querier.with_balance(&[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(amount),
+                }],
+            )]);
+        };
+
+        // Boosted deposit first, then 50 UST of yield before an unboosted deposit
+        set_balance(&mut deps, 100000000u128);
+        let info = mock_info("addr1", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Deposit { lock_period: Some(600u64) },
+        )
+        .unwrap();
+
+        set_balance(&mut deps, 250000000u128);
+        let info = mock_info("addr", &[Coin { denom: "uusd".to_string(), amount: principal }]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { lock_period: None })
+            .unwrap();
+
+        let total_balance = Uint128::from(280000000u128);
+        set_balance(&mut deps, total_balance.u128());
+
+        let config = read_config(deps.as_ref().storage).unwrap();
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+
+        let balances = ["addr", "addr1"]
+            .iter()
+            .map(|addr| {
+                let deposit_info: DepositInfoResponse = from_binary(
+                    &query(
+                        deps.as_ref(),
+                        mock_env(),
+                        QueryMsg::DepositInfo {
+                            addr: String::from(*addr),
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+                get_balance_by_share(
+                    deps.as_ref(),
+                    config.clone(),
+                    state.clone(),
+                    deposit_info.share,
+                    deposit_info.boost,
+                    deposit_info.principal - deposit_info.principal_claimed,
+                    mock_env().block.time.seconds(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<Uint128>>();
+
+        // Unweighted, the deposits would hold 112 and 168 UST,
+        // their 12 and 68 UST of yield are reweighted 1:2 to share the 80 UST of the pool
+        assert_eq!(
+            balances,
+            vec![
+                Uint128::from(106486485u128),
+                Uint128::from(173513513u128)
+            ]
+        );
+        assert!(balances[0] + balances[1] <= total_balance);
+    }
+
     fn instantiate_contract(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
         let msg = InstantiateMsg {
             controller: String::from("controller"),
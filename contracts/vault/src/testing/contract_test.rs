@@ -4,7 +4,9 @@ mod tests {
     use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{from_binary, Coin, Decimal, OwnedDeps, StdError, Uint128};
-    use athena::vault::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, State};
+    use athena::vault::{
+        ConfigResponse, ExecuteMsg, FeeRecipient, InstantiateMsg, QueryMsg, State, StrategyInfo,
+    };
 
     #[test]
     fn instantiate_sets_the_correct_state() {
@@ -20,11 +22,21 @@ mod tests {
             ConfigResponse {
                 contract_addr: String::from(MOCK_CONTRACT_ADDR),
                 controller: String::from("controller"),
-                strategy: None,
                 stable_denom: String::from("uusd"),
                 invest_percentage: Decimal::percent(95u64),
                 lock_period: 100u64,
                 force_withdraw: false,
+                early_withdraw: false,
+                early_withdraw_penalty: Decimal::zero(),
+                penalty_recipient: FeeRecipient::Depositors,
+                auto_invest: false,
+                auto_invest_threshold: Uint128::zero(),
+                migration_tolerance: Decimal::percent(1u64),
+                profit_unlock_period: 21600u64,
+                management_fee: Decimal::zero(),
+                deposit_fee: Decimal::zero(),
+                withdraw_fee: Decimal::zero(),
+                fee_recipient: FeeRecipient::Treasury,
             },
             config
         );
@@ -36,7 +48,17 @@ mod tests {
         assert_eq!(
             State {
                 total_share: Uint128::zero(),
+                total_boosted_share: Uint128::zero(),
+                total_principal: Uint128::zero(),
+                total_boosted_principal: Uint128::zero(),
                 total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
             },
             state
         );
@@ -113,6 +135,32 @@ mod tests {
         assert_eq!(Uint128::from(300u128), total_balance);
     }
 
+    #[test]
+    fn underlying_balance_is_net_of_strategy_accrued_fee() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(200u128),
+            }],
+        )]);
+
+        update_contract_with_stategy(&mut deps, "strategy".to_string());
+
+        deps.querier.with_invested_balance(&Uint128::from(100u128));
+        deps.querier
+            .with_strategy_accrued_fees(&[(&String::from("strategy"), &Uint128::from(5u128))]);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap();
+        let total_balance: Uint128 = from_binary(&res).unwrap();
+
+        assert_eq!(Uint128::from(295u128), total_balance);
+    }
+
     #[test]
     fn available_balance_is_0_when_no_strategy_is_set() {
         let mut deps = mock_dependencies_with_querier(20, &[]);
@@ -207,12 +255,12 @@ mod tests {
         deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
         strategy: String,
     ) {
-        let msg = ExecuteMsg::UpdateConfig {
-            controller: None,
-            strategy: Some(strategy),
-            invest_percentage: None,
-            force_withdraw: None,
-            lock_period: None,
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![StrategyInfo {
+                addr: strategy,
+                weight: Decimal::one(),
+                debt_limit: Uint128::from(u128::MAX),
+            }],
         };
 
         let info = mock_info("governance", &[]);
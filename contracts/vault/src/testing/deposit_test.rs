@@ -5,10 +5,15 @@ mod tests {
     use crate::testing::mock_querier::WasmMockQuerier;
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{
-        attr, from_binary, BankMsg, Coin, CosmosMsg, Decimal, Env, MessageInfo, OwnedDeps,
-        StdError, SubMsg, Uint128,
+        attr, from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, Env, MessageInfo,
+        OwnedDeps, StdError, SubMsg, Uint128, WasmMsg,
     };
-    use athena::vault::{DepositInfoResponse, ExecuteMsg, InstantiateMsg, QueryMsg, State};
+    use athena::vault::{
+        DepositInfoResponse, EarlyWithdrawPenaltyResponse, ExecuteMsg, FeesResponse,
+        InstantiateMsg, LockTier, PreviewDepositResponse, PreviewWithdrawResponse,
+        QueryMsg, State, StrategyInfo,
+    };
+    use athena::vault_strategy::ExecuteMsg as StrategyExecuteMsg;
 
     #[test]
     fn withdraw_to_user() {
@@ -65,15 +70,27 @@ mod tests {
 
         let msg = ExecuteMsg::UpdateConfig {
             controller: None,
-            strategy: Some(String::from("strategy")),
             invest_percentage: None,
             force_withdraw: Some(true),
             lock_period: Some(200u64),
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let governance_info = mock_info("governance", &[]);
 
         execute(deps.as_mut(), mock_env(), governance_info.clone(), msg).unwrap();
+        update_strategy(&mut deps);
 
         let info = mock_info(
             "addr0000",
@@ -140,15 +157,27 @@ mod tests {
 
         let msg = ExecuteMsg::UpdateConfig {
             controller: None,
-            strategy: Some(String::from("strategy")),
             invest_percentage: None,
             force_withdraw: Some(true),
             lock_period: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let governance_info = mock_info("governance", &[]);
 
         execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap();
+        update_strategy(&mut deps);
 
         let info = mock_info(
             "addr0000",
@@ -158,7 +187,7 @@ mod tests {
             }],
         );
 
-        let msg = ExecuteMsg::Deposit {};
+        let msg = ExecuteMsg::Deposit { lock_period: None };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -283,15 +312,27 @@ mod tests {
 
         let msg = ExecuteMsg::UpdateConfig {
             controller: None,
-            strategy: Some(String::from("strategy")),
             invest_percentage: None,
             force_withdraw: None,
             lock_period: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let governance_info = mock_info("governance", &[]);
 
         execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap();
+        update_strategy(&mut deps);
 
         let info = mock_info(
             "addr0000",
@@ -357,6 +398,93 @@ mod tests {
         assert_eq!(res, StdError::generic_err("Still locked"));
     }
 
+    #[test]
+    fn withdraw_before_maturity_pays_early_withdraw_penalty() {
+        let mut deps = dependencies_with_balance();
+        let balance = Uint128::from(100000000u128);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            force_withdraw: None,
+            lock_period: None,
+            early_withdraw: Some(true),
+            early_withdraw_penalty: Some(Decimal::percent(10u64)),
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: balance,
+            }],
+        );
+
+        execute_deposit(&mut deps, info);
+
+        // half of the lock period remains, so half of the penalty applies
+        let mut env = mock_env();
+        add_block_by_seconds(&mut env, 100u64);
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EarlyWithdrawPenalty {
+                addr: String::from("addr0000"),
+            },
+        )
+        .unwrap();
+        let penalty: EarlyWithdrawPenaltyResponse = from_binary(&res).unwrap();
+
+        assert_eq!(penalty.penalty_rate, Decimal::percent(5u64));
+
+        let withdraw_msg = ExecuteMsg::Withdraw {
+            withdraw_amount: balance,
+            force_withdraw: false,
+        };
+
+        let res = execute(deps.as_mut(), env, mock_info("addr0000", &[]), withdraw_msg).unwrap();
+
+        let penalty_amount = balance * Decimal::percent(5u64);
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps
+                        .querier
+                        .deduct_tax(balance - penalty_amount)
+                        .unwrap()
+                }],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "withdraw"),
+                attr("amount", balance),
+                attr("penalty", penalty_amount),
+                attr("withdraw_fee", "0"),
+            ]
+        );
+    }
+
     #[test]
     fn deposit_updates_the_state_for_the_first_deposit() {
         let mut deps = dependencies_with_balance();
@@ -371,7 +499,7 @@ mod tests {
             }],
         );
 
-        let deposit_msg = ExecuteMsg::Deposit {};
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
 
         let env = mock_env();
 
@@ -382,6 +510,7 @@ mod tests {
             vec![
                 attr("action", "deposit"),
                 attr("amount", Uint128::from(100000000u128)),
+                attr("deposit_fee", "0"),
                 attr("share", Uint128::from(100000000u128)),
                 attr("maturity", (env.block.time.seconds() + 200u64).to_string()),
             ]
@@ -394,7 +523,17 @@ mod tests {
         assert_eq!(
             State {
                 total_share: Uint128::from(100000000u128),
+                total_boosted_share: Uint128::from(100000000u128),
+                total_principal: Uint128::from(100000000u128),
+                total_boosted_principal: Uint128::from(100000000u128),
                 total_subsidized: Uint128::from(0u128),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
             },
             state
         );
@@ -413,6 +552,8 @@ mod tests {
         assert_eq!(
             DepositInfoResponse {
                 share: Uint128::from(100000000u128),
+                boost: Decimal::one(),
+                lock_period: 200u64,
                 maturity: env.block.time.seconds() + 200u64,
                 current_amount: Uint128::from(100000000u128),
                 principal: Uint128::from(100000000u128),
@@ -424,6 +565,171 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deposit_with_lock_tier_applies_boost_and_maturity() {
+        let mut deps = dependencies_with_balance();
+
+        instantiate_contract(&mut deps);
+        update_lock_tiers(&mut deps);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: Some(600u64) };
+
+        let env = mock_env();
+
+        execute(deps.as_mut(), env.clone(), info_with_uusd(), deposit_msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+
+        let state: State = from_binary(&res).unwrap();
+
+        assert_eq!(
+            State {
+                total_share: Uint128::from(100000000u128),
+                total_boosted_share: Uint128::from(150000000u128),
+                total_principal: Uint128::from(100000000u128),
+                total_boosted_principal: Uint128::from(150000000u128),
+                total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
+            },
+            state
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DepositInfo {
+                addr: String::from("addr"),
+            },
+        )
+        .unwrap();
+
+        let deposit_info: DepositInfoResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            DepositInfoResponse {
+                share: Uint128::from(100000000u128),
+                boost: Decimal::percent(150u64),
+                lock_period: 600u64,
+                maturity: env.block.time.seconds() + 600u64,
+                current_amount: Uint128::from(100000000u128),
+                principal: Uint128::from(100000000u128),
+                principal_claimed: Uint128::zero(),
+                yield_amount: Uint128::zero(),
+                yield_claimed: Uint128::zero(),
+            },
+            deposit_info
+        );
+    }
+
+    #[test]
+    fn deposit_fails_if_lock_tier_is_invalid() {
+        let mut deps = dependencies_with_balance();
+
+        instantiate_contract(&mut deps);
+        update_lock_tiers(&mut deps);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: Some(300u64) };
+
+        let res = execute(deps.as_mut(), mock_env(), info_with_uusd(), deposit_msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("Invalid lock tier"));
+    }
+
+    #[test]
+    fn deposit_fails_if_lock_tier_changes() {
+        let mut deps = dependencies_with_balance();
+
+        instantiate_contract(&mut deps);
+        update_lock_tiers(&mut deps);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: Some(600u64) };
+        execute(deps.as_mut(), mock_env(), info_with_uusd(), deposit_msg).unwrap();
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
+        let res = execute(deps.as_mut(), mock_env(), info_with_uusd(), deposit_msg).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("Cannot change lock tier of an existing deposit")
+        );
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: Some(600u64) };
+        execute(deps.as_mut(), mock_env(), info_with_uusd(), deposit_msg).unwrap();
+    }
+
+    #[test]
+    fn deposit_auto_invests_available_amount() {
+        let mut deps = dependencies_with_balance();
+
+        instantiate_contract(&mut deps);
+        update_auto_invest(&mut deps, Uint128::from(10000000u128));
+
+        let env = mock_env();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info_with_uusd(),
+            ExecuteMsg::Deposit { lock_period: None },
+        )
+        .unwrap();
+
+        // 95% of the vault balance is available
+        let invest_amount = Uint128::from(142500000u128);
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("strategy"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(invest_amount).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("strategy"),
+                    msg: to_binary(&StrategyExecuteMsg::Deploy {}).unwrap(),
+                    funds: vec![],
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "deposit"),
+                attr("amount", Uint128::from(100000000u128)),
+                attr("deposit_fee", "0"),
+                attr("share", Uint128::from(100000000u128)),
+                attr("maturity", (env.block.time.seconds() + 200u64).to_string()),
+                attr("auto_invest", invest_amount),
+            ]
+        );
+    }
+
+    #[test]
+    fn deposit_does_not_auto_invest_below_threshold() {
+        let mut deps = dependencies_with_balance();
+
+        instantiate_contract(&mut deps);
+        update_auto_invest(&mut deps, Uint128::from(200000000u128));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info_with_uusd(),
+            ExecuteMsg::Deposit { lock_period: None },
+        )
+        .unwrap();
+
+        assert_eq!(res.messages.len(), 0);
+    }
+
     #[test]
     fn deposit_updates_the_state_for_a_second_deposit() {
         let mut deps = dependencies_with_balance();
@@ -438,7 +744,7 @@ mod tests {
             }],
         );
 
-        let deposit_1_msg = ExecuteMsg::Deposit {};
+        let deposit_1_msg = ExecuteMsg::Deposit { lock_period: None };
 
         execute(deps.as_mut(), mock_env(), info, deposit_1_msg).unwrap();
 
@@ -450,7 +756,7 @@ mod tests {
             }],
         )]);
 
-        let deposit_2_msg = ExecuteMsg::Deposit {};
+        let deposit_2_msg = ExecuteMsg::Deposit { lock_period: None };
 
         let info = mock_info(
             "addr",
@@ -469,6 +775,7 @@ mod tests {
             vec![
                 attr("action", "deposit"),
                 attr("amount", Uint128::from(50000000u128)),
+                attr("deposit_fee", "0"),
                 attr("share", Uint128::from(33333333u128)),
                 attr("maturity", (env.block.time.seconds() + 200u64).to_string()),
             ]
@@ -481,7 +788,17 @@ mod tests {
         assert_eq!(
             State {
                 total_share: Uint128::from(133333333u128),
+                total_boosted_share: Uint128::from(133333333u128),
+                total_principal: Uint128::from(150000000u128),
+                total_boosted_principal: Uint128::from(150000000u128),
                 total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
             },
             state
         );
@@ -502,6 +819,8 @@ mod tests {
                 principal: Uint128::from(150000000u128),
                 current_amount: Uint128::from(150000000u128),
                 share: Uint128::from(33333333u128),
+                boost: Decimal::one(),
+                lock_period: 200u64,
                 maturity: env.block.time.seconds() + 200u64,
                 yield_amount: Uint128::zero(),
                 yield_claimed: Uint128::zero(),
@@ -511,13 +830,296 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deposit_does_not_capture_locked_profit() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_strategy(&mut deps);
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        )]);
+        execute_deposit(&mut deps, info_with_uusd());
+
+        let report_msg = ExecuteMsg::Report {
+            gain: Uint128::from(10000000u128),
+            loss: Uint128::zero(),
+            debt_payment: Uint128::zero(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("strategy", &[]), report_msg).unwrap();
+
+        deps.querier
+            .with_invested_balance(&Uint128::from(10000000u128));
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(200000000u128),
+            }],
+        )]);
+
+        let deposit_msg = ExecuteMsg::Deposit { lock_period: None };
+        let info = mock_info(
+            "addr2",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        );
+
+        // The whole gain is locked right after the report
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), deposit_msg.clone()).unwrap();
+        assert_eq!(res.attributes[3], attr("share", Uint128::from(100000000u128)));
+
+        // Half of the gain is released after half of the unlock period
+        let mut env = mock_env();
+        add_block_by_seconds(&mut env, 10800u64);
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(300000000u128),
+            }],
+        )]);
+
+        let res = execute(deps.as_mut(), env, info, deposit_msg).unwrap();
+        assert_eq!(res.attributes[3], attr("share", Uint128::from(97560975u128)));
+    }
+
+    #[test]
+    fn deposit_accrues_and_pays_management_fee() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: Some(Decimal::percent(10u64)),
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        )]);
+        execute_deposit(&mut deps, info_with_uusd());
+
+        // Half a year later
+        let mut env = mock_env();
+        add_block_by_seconds(&mut env, 15768000u64);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Fees {}).unwrap();
+        let fees: FeesResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            FeesResponse {
+                management_fee: Decimal::percent(10u64),
+                management_fee_payable: Uint128::from(5000000u128),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: env.block.time.seconds(),
+            },
+            fees
+        );
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(200000000u128),
+            }],
+        )]);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info_with_uusd(),
+            ExecuteMsg::Deposit { lock_period: None },
+        )
+        .unwrap();
+
+        // The payable fee is not part of the share pool
+        assert_eq!(res.attributes[3], attr("share", Uint128::from(105263157u128)));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps.querier.deduct_tax(Uint128::from(5000000u128)).unwrap(),
+                }],
+            }))]
+        );
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Fees {}).unwrap();
+        let fees: FeesResponse = from_binary(&res).unwrap();
+
+        assert_eq!(fees.management_fee_payable, Uint128::zero());
+        assert_eq!(fees.management_fee_paid, Uint128::from(5000000u128));
+    }
+
+    #[test]
+    fn deposit_and_withdraw_pay_fees_to_treasury() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: Some(Decimal::percent(1u64)),
+            withdraw_fee: Some(Decimal::percent(2u64)),
+            fee_recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PreviewDeposit {
+                amount: Uint128::from(100000000u128),
+            },
+        )
+        .unwrap();
+        let preview: PreviewDepositResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            PreviewDepositResponse {
+                deposit_fee: Uint128::from(1000000u128),
+                principal: Uint128::from(99000000u128),
+                share: Uint128::from(99000000u128),
+            },
+            preview
+        );
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        )]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info_with_uusd(),
+            ExecuteMsg::Deposit { lock_period: None },
+        )
+        .unwrap();
+
+        assert_eq!(res.attributes[2], attr("deposit_fee", Uint128::from(1000000u128)));
+        assert_eq!(res.attributes[3], attr("share", Uint128::from(99000000u128)));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps.querier.deduct_tax(Uint128::from(1000000u128)).unwrap(),
+                }],
+            }))]
+        );
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99000000u128),
+            }],
+        )]);
+
+        let mut env = mock_env();
+        add_block_by_seconds(&mut env, 620u64);
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::PreviewWithdraw {
+                addr: String::from("addr"),
+                amount: Uint128::from(99000000u128),
+            },
+        )
+        .unwrap();
+        let preview: PreviewWithdrawResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            PreviewWithdrawResponse {
+                withdraw_fee: Uint128::from(1980000u128),
+                penalty: Uint128::zero(),
+                return_amount: Uint128::from(97020000u128),
+            },
+            preview
+        );
+
+        let withdraw_msg = ExecuteMsg::Withdraw {
+            withdraw_amount: Uint128::from(99000000u128),
+            force_withdraw: false,
+        };
+
+        let res = execute(deps.as_mut(), env, mock_info("addr", &[]), withdraw_msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("addr"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(Uint128::from(97020000u128)).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("treasury"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(Uint128::from(1980000u128)).unwrap(),
+                    }],
+                })),
+            ]
+        );
+        assert_eq!(res.attributes[3], attr("withdraw_fee", Uint128::from(1980000u128)));
+    }
+
     #[test]
     fn deposit_check_deposit_amount() {
         let mut deps = dependencies_with_balance();
 
         instantiate_contract(&mut deps);
 
-        let msg = ExecuteMsg::Deposit {};
+        let msg = ExecuteMsg::Deposit { lock_period: None };
 
         let info = mock_info(
             "addr",
@@ -541,7 +1143,7 @@ mod tests {
 
         instantiate_contract(&mut deps);
 
-        let msg = ExecuteMsg::Deposit {};
+        let msg = ExecuteMsg::Deposit { lock_period: None };
 
         let info = mock_info(
             "addr",
@@ -575,11 +1177,76 @@ mod tests {
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
     }
 
+    fn update_lock_tiers(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: Some(vec![LockTier {
+                lock_period: 600u64,
+                boost: Decimal::percent(150u64),
+            }]),
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+    }
+
+    fn update_auto_invest(
+        deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+        auto_invest_threshold: Uint128,
+    ) {
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: Some(true),
+            auto_invest_threshold: Some(auto_invest_threshold),
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+        update_strategy(deps);
+    }
+
+    fn update_strategy(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![StrategyInfo {
+                addr: String::from("strategy"),
+                weight: Decimal::one(),
+                debt_limit: Uint128::from(u128::MAX),
+            }],
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+    }
+
     fn execute_deposit(
         deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
         info: MessageInfo,
     ) {
-        let msg = ExecuteMsg::Deposit {};
+        let msg = ExecuteMsg::Deposit { lock_period: None };
 
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     }
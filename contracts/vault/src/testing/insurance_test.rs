@@ -0,0 +1,225 @@
+#[cfg(test)]
+mod tests {
+    use crate::contract::{execute, instantiate, query};
+    use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
+    use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{attr, from_binary, Coin, Decimal, OwnedDeps, StdError, Uint128};
+    use athena::vault::{DepositInfoResponse, ExecuteMsg, InstantiateMsg, QueryMsg, State};
+
+    #[test]
+    fn fund_insurance_fails_if_nothing_is_sent() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("strategy", &[]),
+            ExecuteMsg::FundInsurance {},
+        )
+        .unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("Fund amount must be greater than 0"));
+    }
+
+    #[test]
+    fn insurance_reserve_is_excluded_from_share_price() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        with_vault_balance(&mut deps, 100000000u128);
+        deposit(&mut deps, "addr", 100000000u128);
+
+        with_vault_balance(&mut deps, 110000000u128);
+        fund_insurance(&mut deps, 10000000u128);
+
+        with_vault_balance(&mut deps, 210000000u128);
+        deposit(&mut deps, "addr1", 100000000u128);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+
+        assert_eq!(
+            State {
+                total_share: Uint128::from(200000000u128),
+                total_boosted_share: Uint128::from(200000000u128),
+                total_principal: Uint128::from(200000000u128),
+                total_boosted_principal: Uint128::from(200000000u128),
+                total_subsidized: Uint128::zero(),
+                insurance_reserve: Uint128::from(10000000u128),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
+            },
+            state
+        );
+    }
+
+    #[test]
+    fn declare_loss_fails_if_sender_is_not_governance() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::DeclareLoss {
+                amount: Uint128::from(100u128),
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn declare_loss_is_covered_by_insurance_before_principal() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        with_vault_balance(&mut deps, 100000000u128);
+        deposit(&mut deps, "addr", 100000000u128);
+
+        with_vault_balance(&mut deps, 110000000u128);
+        fund_insurance(&mut deps, 10000000u128);
+
+        // 30 UST is lost, 10 UST is covered by the reserve
+        with_vault_balance(&mut deps, 80000000u128);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("governance", &[]),
+            ExecuteMsg::DeclareLoss {
+                amount: Uint128::from(30000000u128),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "declare_loss"),
+                attr("amount", Uint128::from(30000000u128)),
+                attr("covered", Uint128::from(10000000u128)),
+                attr("haircut", Uint128::from(20000000u128)),
+                attr("principal_index", "0.8"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+
+        assert_eq!(state.insurance_reserve, Uint128::zero());
+        assert_eq!(state.principal_index, Decimal::percent(80u64));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DepositInfo {
+                addr: String::from("addr"),
+            },
+        )
+        .unwrap();
+        let deposit_info: DepositInfoResponse = from_binary(&res).unwrap();
+
+        assert_eq!(deposit_info.principal, Uint128::from(80000000u128));
+    }
+
+    #[test]
+    fn declare_loss_fails_if_amount_exceeds_shortfall() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        with_vault_balance(&mut deps, 100000000u128);
+        deposit(&mut deps, "addr", 100000000u128);
+
+        with_vault_balance(&mut deps, 110000000u128);
+        fund_insurance(&mut deps, 10000000u128);
+
+        // Pool balance of 70 UST is 30 UST short of the principal
+        with_vault_balance(&mut deps, 80000000u128);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("governance", &[]),
+            ExecuteMsg::DeclareLoss {
+                amount: Uint128::from(30000001u128),
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Loss exceeds shortfall of 30000000")
+        );
+    }
+
+    fn instantiate_contract(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+        let msg = InstantiateMsg {
+            controller: String::from("controller"),
+            stable_denom: String::from("uusd"),
+            invest_percentage: Decimal::percent(95u64),
+            lock_period: 100u64,
+        };
+
+        instantiate(deps.as_mut(), mock_env(), mock_info("addr", &[]), msg).unwrap();
+    }
+
+    fn with_vault_balance(
+        deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+        amount: u128,
+    ) {
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        )]);
+    }
+
+    fn deposit(
+        deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+        sender: &str,
+        amount: u128,
+    ) {
+        let info = mock_info(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Deposit { lock_period: None },
+        )
+        .unwrap();
+    }
+
+    fn fund_insurance(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, amount: u128) {
+        let info = mock_info(
+            "strategy",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        );
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::FundInsurance {}).unwrap();
+    }
+}
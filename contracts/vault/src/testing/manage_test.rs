@@ -4,8 +4,16 @@ mod tests {
     use crate::contract::{execute, instantiate, query};
     use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{attr, from_binary, Coin, Decimal, OwnedDeps, StdError, Uint128};
-    use athena::vault::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+    use cosmwasm_std::{
+        attr, from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, OwnedDeps, StdError, SubMsg,
+        Uint128, WasmMsg,
+    };
+    use athena::vault::{
+        ConfigResponse, ExecuteMsg, FeeRecipient, InstantiateMsg, LockTier, LockTiersResponse,
+        QueryMsg, RetiredStrategiesResponse, RetiredStrategyResponse, State, StrategiesResponse,
+        StrategyCreditResponse, StrategyDebtResponse, StrategyInfo,
+    };
+    use athena::vault_strategy::ExecuteMsg as StrategyExecuteMsg;
 
     #[test]
     fn update_config_fails_if_sender_is_unauthorized() {
@@ -15,10 +23,21 @@ mod tests {
 
         let msg = ExecuteMsg::UpdateConfig {
             controller: Some(String::from("controller2")),
-            strategy: Some(String::from("strategy")),
             invest_percentage: Some(Decimal::percent(90u64)),
             lock_period: Some(600u64),
             force_withdraw: Some(true),
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let info = mock_info("addr", &[]);
@@ -37,10 +56,21 @@ mod tests {
         // return an error if the invest_percentage is greater than 100%
         let msg = ExecuteMsg::UpdateConfig {
             controller: Some(String::from("controller2")),
-            strategy: Some(String::from("strategy")),
             invest_percentage: Some(Decimal::percent(101u64)),
             lock_period: Some(600u64),
             force_withdraw: Some(true),
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let governance_info = mock_info("governance", &[]);
@@ -53,6 +83,149 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_config_fails_if_early_withdraw_penalty_is_greater_than_100() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: Some(true),
+            early_withdraw_penalty: Some(Decimal::percent(101u64)),
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        let governance_info = mock_info("governance", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Early withdraw penalty must be less than 100%")
+        );
+    }
+
+    #[test]
+    fn update_config_fails_if_management_fee_is_greater_than_100() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: Some(Decimal::percent(101u64)),
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        let governance_info = mock_info("governance", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Management fee must be less than 100%")
+        );
+    }
+
+    #[test]
+    fn update_config_fails_if_withdraw_fee_is_greater_than_100() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: None,
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: Some(Decimal::percent(1u64)),
+            withdraw_fee: Some(Decimal::percent(101u64)),
+            fee_recipient: None,
+        };
+
+        let governance_info = mock_info("governance", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Withdraw fee must be less than 100%")
+        );
+    }
+
+    #[test]
+    fn update_config_fails_if_lock_tier_boost_is_less_than_1() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            invest_percentage: None,
+            lock_period: None,
+            force_withdraw: None,
+            early_withdraw: None,
+            early_withdraw_penalty: None,
+            penalty_recipient: None,
+            lock_tiers: Some(vec![LockTier {
+                lock_period: 600u64,
+                boost: Decimal::percent(90u64),
+            }]),
+            auto_invest: None,
+            auto_invest_threshold: None,
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
+        };
+
+        let governance_info = mock_info("governance", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), governance_info, msg).unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Lock tier boost must be at least 1")
+        );
+    }
+
     #[test]
     fn update_config() {
         let mut deps = mock_dependencies_with_querier(20, &[]);
@@ -61,10 +234,24 @@ mod tests {
 
         let msg = ExecuteMsg::UpdateConfig {
             controller: Some(String::from("controller2")),
-            strategy: Some(String::from("strategy")),
             invest_percentage: Some(Decimal::percent(90u64)),
             lock_period: Some(600u64),
             force_withdraw: Some(true),
+            early_withdraw: Some(true),
+            early_withdraw_penalty: Some(Decimal::percent(10u64)),
+            penalty_recipient: Some(FeeRecipient::Treasury),
+            lock_tiers: Some(vec![LockTier {
+                lock_period: 1200u64,
+                boost: Decimal::percent(150u64),
+            }]),
+            auto_invest: Some(true),
+            auto_invest_threshold: Some(Uint128::from(1000000u128)),
+            migration_tolerance: None,
+            profit_unlock_period: None,
+            management_fee: None,
+            deposit_fee: None,
+            withdraw_fee: None,
+            fee_recipient: None,
         };
 
         let governance_info = mock_info("governance", &[]);
@@ -81,14 +268,38 @@ mod tests {
             ConfigResponse {
                 contract_addr: String::from(MOCK_CONTRACT_ADDR),
                 controller: String::from("controller2"),
-                strategy: Some(String::from("strategy")),
                 stable_denom: String::from("uusd"),
                 invest_percentage: Decimal::percent(90u64),
                 lock_period: 600u64,
                 force_withdraw: true,
+                early_withdraw: true,
+                early_withdraw_penalty: Decimal::percent(10u64),
+                penalty_recipient: FeeRecipient::Treasury,
+                auto_invest: true,
+                auto_invest_threshold: Uint128::from(1000000u128),
+                migration_tolerance: Decimal::percent(1u64),
+                profit_unlock_period: 21600u64,
+                management_fee: Decimal::zero(),
+                deposit_fee: Decimal::zero(),
+                withdraw_fee: Decimal::zero(),
+                fee_recipient: FeeRecipient::Treasury,
             },
             config
         );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::LockTiers {}).unwrap();
+
+        let lock_tiers: LockTiersResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            LockTiersResponse {
+                lock_tiers: vec![LockTier {
+                    lock_period: 1200u64,
+                    boost: Decimal::percent(150u64),
+                }],
+            },
+            lock_tiers
+        );
     }
 
     #[test]
@@ -192,6 +403,656 @@ mod tests {
         assert_eq!(vault_balance, total_balance);
     }
 
+    #[test]
+    fn update_strategies_fails_if_sender_is_not_governance() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateStrategies { strategies: vec![] };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn update_strategies_fails_if_total_weight_is_greater_than_100() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![
+                StrategyInfo {
+                    addr: String::from("strategy"),
+                    weight: Decimal::percent(60u64),
+                    debt_limit: Uint128::from(100000000u128),
+                },
+                StrategyInfo {
+                    addr: String::from("strategy2"),
+                    weight: Decimal::percent(50u64),
+                    debt_limit: Uint128::from(100000000u128),
+                },
+            ],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg)
+            .unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Total strategy weight must be less than 100%")
+        );
+    }
+
+    #[test]
+    fn update_strategies_fails_if_strategy_is_duplicated() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let strategy = StrategyInfo {
+            addr: String::from("strategy"),
+            weight: Decimal::percent(50u64),
+            debt_limit: Uint128::from(100000000u128),
+        };
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![strategy.clone(), strategy],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg)
+            .unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("Duplicate strategy"));
+    }
+
+    #[test]
+    fn update_strategies_fails_to_remove_strategy_holding_funds() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        deps.querier.with_invested_balance(&Uint128::from(100000000u128));
+
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![StrategyInfo {
+                addr: String::from("strategy2"),
+                weight: Decimal::one(),
+                debt_limit: Uint128::from(100000000u128),
+            }],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg)
+            .unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Strategy holds funds, use MigrateStrategy")
+        );
+    }
+
+    #[test]
+    fn invest_splits_available_amount_by_weight_and_debt_limit() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![
+                StrategyInfo {
+                    addr: String::from("strategy"),
+                    weight: Decimal::percent(60u64),
+                    debt_limit: Uint128::from(1000000000u128),
+                },
+                StrategyInfo {
+                    addr: String::from("strategy2"),
+                    weight: Decimal::percent(40u64),
+                    debt_limit: Uint128::from(30000000u128),
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        )]);
+        deps.querier.with_strategy_balances(&[
+            (&String::from("strategy"), &Uint128::from(20000000u128)),
+            (&String::from("strategy2"), &Uint128::from(10000000u128)),
+        ]);
+
+        // target = 130000000 * 95% = 123500000
+        // strategy: 123500000 * 60% - 20000000 = 54100000
+        // strategy2: min(123500000 * 40%, 30000000) - 10000000 = 20000000
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::Invest {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("strategy"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(Uint128::from(54100000u128)).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("strategy2"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(Uint128::from(20000000u128)).unwrap(),
+                    }],
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "invest"), attr("amount", "74100000")]
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StrategyDebt {
+                strategy: String::from("strategy2"),
+            },
+        )
+        .unwrap();
+        let strategy_debt: StrategyDebtResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            StrategyDebtResponse {
+                strategy: String::from("strategy2"),
+                total_debt: Uint128::from(20000000u128),
+                total_gain: Uint128::zero(),
+                total_loss: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                debt_limit: Uint128::from(30000000u128),
+            },
+            strategy_debt
+        );
+
+        // Mock strategy balances do not change by Invest
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StrategyCredit {
+                strategy: String::from("strategy2"),
+            },
+        )
+        .unwrap();
+        let strategy_credit: StrategyCreditResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            StrategyCreditResponse {
+                strategy: String::from("strategy2"),
+                balance: Uint128::from(10000000u128),
+                credit_available: Uint128::from(20000000u128),
+            },
+            strategy_credit
+        );
+    }
+
+    #[test]
+    fn strategy_debt_does_not_query_strategies() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        // Strategies query their debt to compute their own balance
+        deps.querier.with_strategies_unavailable();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StrategyDebt {
+                strategy: String::from("strategy"),
+            },
+        )
+        .unwrap();
+        let strategy_debt: StrategyDebtResponse = from_binary(&res).unwrap();
+        assert_eq!(strategy_debt.total_debt, Uint128::zero());
+    }
+
+    #[test]
+    fn invest_uses_strategy_balance_net_of_accrued_fee() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![
+                StrategyInfo {
+                    addr: String::from("strategy"),
+                    weight: Decimal::percent(60u64),
+                    debt_limit: Uint128::from(1000000000u128),
+                },
+                StrategyInfo {
+                    addr: String::from("strategy2"),
+                    weight: Decimal::percent(40u64),
+                    debt_limit: Uint128::from(30000000u128),
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u128),
+            }],
+        )]);
+        deps.querier.with_strategy_balances(&[
+            (&String::from("strategy"), &Uint128::from(20000000u128)),
+            (&String::from("strategy2"), &Uint128::from(10000000u128)),
+        ]);
+        deps.querier
+            .with_strategy_accrued_fees(&[(&String::from("strategy"), &Uint128::from(10000000u128))]);
+
+        // target = 120000000 * 95% = 114000000
+        // strategy: 114000000 * 60% - 10000000 = 58400000
+        // strategy2: min(114000000 * 40%, 30000000) - 10000000 = 20000000
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::Invest {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "invest"), attr("amount", "78400000")]
+        );
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("strategy"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: deps.querier.deduct_tax(Uint128::from(58400000u128)).unwrap(),
+                }],
+            }))
+        );
+    }
+
+    #[test]
+    fn report_fails_if_sender_is_not_strategy() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        let msg = ExecuteMsg::Report {
+            gain: Uint128::from(1000000u128),
+            loss: Uint128::zero(),
+            debt_payment: Uint128::zero(),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn report_fails_if_debt_payment_does_not_match_sent_funds() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        let msg = ExecuteMsg::Report {
+            gain: Uint128::zero(),
+            loss: Uint128::zero(),
+            debt_payment: Uint128::from(10000000u128),
+        };
+        let info = mock_info(
+            "strategy",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(9900000u128),
+            }],
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Debt payment must match sent funds")
+        );
+    }
+
+    #[test]
+    fn report_updates_strategy_debt_and_locks_gain() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(200000000u128),
+            }],
+        )]);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::Invest {},
+        )
+        .unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1000u64);
+
+        let msg = ExecuteMsg::Report {
+            gain: Uint128::from(5000000u128),
+            loss: Uint128::from(1000000u128),
+            debt_payment: Uint128::from(10000000u128),
+        };
+        let info = mock_info(
+            "strategy",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(10000000u128),
+            }],
+        );
+
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "report"),
+                attr("strategy", "strategy"),
+                attr("gain", "5000000"),
+                attr("loss", "1000000"),
+                attr("debt_payment", "10000000"),
+                attr("locked_profit", "4000000"),
+            ]
+        );
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::StrategyDebt {
+                strategy: String::from("strategy"),
+            },
+        )
+        .unwrap();
+        let strategy_debt: StrategyDebtResponse = from_binary(&res).unwrap();
+
+        assert_eq!(strategy_debt.total_debt, Uint128::from(179000000u128));
+        assert_eq!(strategy_debt.total_gain, Uint128::from(5000000u128));
+        assert_eq!(strategy_debt.total_loss, Uint128::from(1000000u128));
+        assert_eq!(strategy_debt.last_report, env.block.time.seconds());
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+
+        assert_eq!(state.locked_profit, Uint128::from(4000000u128));
+        assert_eq!(state.last_report, env.block.time.seconds());
+    }
+
+    #[test]
+    fn migrate_strategy_fails_if_sender_is_not_governance() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        let msg = ExecuteMsg::MigrateStrategy {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn migrate_strategy_withdraws_all_before_switching() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        let expected_amount = Uint128::from(100000000u128);
+        let previous_balance = Uint128::from(5000000u128);
+
+        deps.querier.with_invested_balance(&expected_amount);
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: previous_balance,
+            }],
+        )]);
+
+        let msg = ExecuteMsg::MigrateStrategy {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("strategy"),
+                    msg: to_binary(&StrategyExecuteMsg::WithdrawAll {}).unwrap(),
+                    funds: vec![],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("strategy"),
+                    msg: to_binary(&StrategyExecuteMsg::WithdrawToVault { amount: None })
+                        .unwrap(),
+                    funds: vec![],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::MigrateStrategyHook {
+                        strategy: String::from("strategy"),
+                        new_strategy: String::from("strategy2"),
+                        expected_amount,
+                        previous_balance,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_strategy_hook_fails_if_sender_is_not_vault() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+
+        let msg = ExecuteMsg::MigrateStrategyHook {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+            expected_amount: Uint128::zero(),
+            previous_balance: Uint128::zero(),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg)
+            .unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn migrate_strategy_hook_fails_if_returned_amount_is_out_of_tolerance() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(98000000u128),
+            }],
+        )]);
+
+        let msg = ExecuteMsg::MigrateStrategyHook {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+            expected_amount: Uint128::from(100000000u128),
+            previous_balance: Uint128::zero(),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg)
+            .unwrap_err();
+
+        assert_eq!(
+            res,
+            StdError::generic_err("Migration returned 98000000 which is less than 99000000")
+        );
+    }
+
+    #[test]
+    fn migrate_strategy_hook_switches_strategy() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(104500000u128),
+            }],
+        )]);
+
+        let msg = ExecuteMsg::MigrateStrategyHook {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+            expected_amount: Uint128::from(100000000u128),
+            previous_balance: Uint128::from(5000000u128),
+        };
+
+        let env = mock_env();
+
+        execute(deps.as_mut(), env.clone(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Strategies {}).unwrap();
+        let strategies: StrategiesResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            StrategiesResponse {
+                strategies: vec![StrategyInfo {
+                    addr: String::from("strategy2"),
+                    weight: Decimal::one(),
+                    debt_limit: Uint128::from(u128::MAX),
+                }],
+            },
+            strategies
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RetiredStrategies {}).unwrap();
+        let retired_strategies: RetiredStrategiesResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            RetiredStrategiesResponse {
+                strategies: vec![RetiredStrategyResponse {
+                    strategy: String::from("strategy"),
+                    retired_at: env.block.time.seconds(),
+                    expected_amount: Uint128::from(100000000u128),
+                    returned_amount: Uint128::from(99500000u128),
+                }],
+            },
+            retired_strategies
+        );
+    }
+
+    #[test]
+    fn report_from_retired_strategy_records_returned_amount() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        instantiate_contract(&mut deps);
+        update_contract_with_stategy(&mut deps, String::from("strategy"));
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99000000u128),
+            }],
+        )]);
+
+        let msg = ExecuteMsg::MigrateStrategyHook {
+            strategy: String::from("strategy"),
+            new_strategy: String::from("strategy2"),
+            expected_amount: Uint128::from(100000000u128),
+            previous_balance: Uint128::zero(),
+        };
+        let env = mock_env();
+        execute(deps.as_mut(), env.clone(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Report {
+            gain: Uint128::zero(),
+            loss: Uint128::zero(),
+            debt_payment: Uint128::from(1000000u128),
+        };
+        let info = mock_info(
+            "strategy",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "report_retired"),
+                attr("strategy", "strategy"),
+                attr("debt_payment", "1000000"),
+                attr("returned_amount", "100000000"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RetiredStrategies {}).unwrap();
+        let retired_strategies: RetiredStrategiesResponse = from_binary(&res).unwrap();
+
+        assert_eq!(
+            RetiredStrategiesResponse {
+                strategies: vec![RetiredStrategyResponse {
+                    strategy: String::from("strategy"),
+                    retired_at: env.block.time.seconds(),
+                    expected_amount: Uint128::from(100000000u128),
+                    returned_amount: Uint128::from(100000000u128),
+                }],
+            },
+            retired_strategies
+        );
+    }
+
     fn instantiate_contract(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
         let msg = InstantiateMsg {
             controller: String::from("controller"),
@@ -209,12 +1070,12 @@ mod tests {
         deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
         strategy: String,
     ) {
-        let msg = ExecuteMsg::UpdateConfig {
-            controller: None,
-            strategy: Some(strategy),
-            invest_percentage: None,
-            force_withdraw: None,
-            lock_period: None,
+        let msg = ExecuteMsg::UpdateStrategies {
+            strategies: vec![StrategyInfo {
+                addr: strategy,
+                weight: Decimal::one(),
+                debt_limit: Uint128::from(u128::MAX),
+            }],
         };
 
         let info = mock_info("governance", &[]);
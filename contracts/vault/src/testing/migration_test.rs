@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod tests {
+    use crate::contract::{migrate, query};
+    use crate::state::{LegacyConfig, LegacyDepositInfo, LegacyState};
+    use crate::testing::mock_querier::mock_dependencies_with_querier;
+    use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{attr, from_binary, Api, Decimal, Response, Uint128};
+    use cosmwasm_storage::{bucket, singleton};
+    use athena::vault::{
+        DepositInfoResponse, MigrateMsg, QueryMsg, State, StrategiesResponse,
+        StrategyDebtResponse, StrategyInfo,
+    };
+
+    #[test]
+    fn migrate_legacy_storage() {
+        let mut deps = mock_dependencies_with_querier(20, &[]);
+
+        let legacy_config = LegacyConfig {
+            contract_addr: deps.api.addr_canonicalize(MOCK_CONTRACT_ADDR).unwrap(),
+            controller: deps.api.addr_canonicalize("controller").unwrap(),
+            stable_denom: String::from("uusd"),
+            strategy: Some(deps.api.addr_canonicalize("strategy").unwrap()),
+            invest_percentage: Decimal::percent(95u64),
+            lock_period: 200u64,
+            force_withdraw: false,
+        };
+        singleton(&mut deps.storage, b"config")
+            .save(&legacy_config)
+            .unwrap();
+        singleton(&mut deps.storage, b"state")
+            .save(&LegacyState {
+                total_share: Uint128::from(300000000u128),
+                total_subsidized: Uint128::from(5000000u128),
+            })
+            .unwrap();
+
+        for (addr, principal, principal_claimed) in [
+            ("addr0000", 100000000u128, 0u128),
+            ("addr0001", 200000000u128, 50000000u128),
+        ] {
+            bucket(&mut deps.storage, b"deposit_info")
+                .save(
+                    addr.as_bytes(),
+                    &LegacyDepositInfo {
+                        principal: Uint128::from(principal),
+                        current_amount: Uint128::from(principal - principal_claimed),
+                        share: Uint128::from(principal - principal_claimed),
+                        maturity: 100u64,
+                        yield_amount: Uint128::zero(),
+                        yield_claimed: Uint128::zero(),
+                        principal_claimed: Uint128::from(principal_claimed),
+                    },
+                )
+                .unwrap();
+        }
+
+        deps.querier
+            .with_strategy_balances(&[(&String::from("strategy"), &Uint128::from(150000000u128))]);
+
+        let msg = MigrateMsg {
+            start_after: None,
+            limit: Some(1u32),
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("deposits", "1"),
+                attr("last_deposit", "addr0000"),
+                attr("total_principal", "100000000"),
+            ]
+        );
+
+        let msg = MigrateMsg {
+            start_after: Some(String::from("addr0000")),
+            limit: None,
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("deposits", "1"),
+                attr("last_deposit", "addr0001"),
+                attr("total_principal", "250000000"),
+            ]
+        );
+
+        // Deposits already migrated are not counted again
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("deposits", "0"),
+                attr("last_deposit", "addr0001"),
+                attr("total_principal", "250000000"),
+            ]
+        );
+
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(
+            state,
+            State {
+                total_share: Uint128::from(300000000u128),
+                total_boosted_share: Uint128::from(300000000u128),
+                total_principal: Uint128::from(250000000u128),
+                total_boosted_principal: Uint128::from(250000000u128),
+                total_subsidized: Uint128::from(5000000u128),
+                insurance_reserve: Uint128::zero(),
+                principal_index: Decimal::one(),
+                locked_profit: Uint128::zero(),
+                last_report: mock_env().block.time.seconds(),
+                management_fee_payable: Uint128::zero(),
+                management_fee_paid: Uint128::zero(),
+                last_fee_accrual: mock_env().block.time.seconds(),
+            }
+        );
+
+        let deposit_info: DepositInfoResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::DepositInfo {
+                    addr: String::from("addr0001"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(deposit_info.boost, Decimal::one());
+        assert_eq!(deposit_info.lock_period, 200u64);
+        assert_eq!(deposit_info.principal_claimed, Uint128::from(50000000u128));
+
+        let strategies: StrategiesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Strategies {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            strategies.strategies,
+            vec![StrategyInfo {
+                addr: String::from("strategy"),
+                weight: Decimal::one(),
+                debt_limit: Uint128::from(u128::MAX),
+            }]
+        );
+
+        let strategy_debt: StrategyDebtResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::StrategyDebt {
+                    strategy: String::from("strategy"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(strategy_debt.total_debt, Uint128::from(150000000u128));
+
+        let msg = MigrateMsg {
+            start_after: None,
+            limit: None,
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res, Response::default());
+    }
+}
@@ -4,6 +4,8 @@ use cosmwasm_std::{
     QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 
+use std::collections::HashMap;
+
 use athena::controller::{
     ConfigResponse as ControllerConfigResponse, QueryMsg as ControllerQueryMsg, UserRole,
 };
@@ -17,6 +19,9 @@ pub struct WasmMockQuerier {
     governance: String,
     treasury: String,
     invested_balance: Uint128,
+    strategy_balances: HashMap<String, Uint128>,
+    strategy_accrued_fees: HashMap<String, Uint128>,
+    strategies_unavailable: bool,
     tax: (Decimal, Uint128),
 }
 
@@ -74,7 +79,7 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr: _,
+                contract_addr,
                 msg,
             }) => match from_binary(&msg) {
                 Ok(ControllerQueryMsg::Config {}) => {
@@ -98,10 +103,31 @@ impl WasmMockQuerier {
                         })))
                     }
                 }
+                _ if self.strategies_unavailable => SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.to_string(),
+                }),
                 _ => match from_binary(&msg) {
-                    Ok(StrategyQueryMsg::TotalBalance { .. }) => SystemResult::Ok(
-                        ContractResult::from(to_binary(&self.invested_balance.clone())),
-                    ),
+                    Ok(StrategyQueryMsg::TotalBalance { .. }) => {
+                        let balance = self
+                            .strategy_balances
+                            .get(contract_addr)
+                            .unwrap_or(&self.invested_balance);
+                        SystemResult::Ok(ContractResult::from(to_binary(balance)))
+                    }
+                    Ok(StrategyQueryMsg::NetTotalBalance { .. }) => {
+                        let balance = self
+                            .strategy_balances
+                            .get(contract_addr)
+                            .unwrap_or(&self.invested_balance);
+                        let accrued_fee = self
+                            .strategy_accrued_fees
+                            .get(contract_addr)
+                            .cloned()
+                            .unwrap_or_default();
+                        SystemResult::Ok(ContractResult::from(to_binary(
+                            &balance.saturating_sub(accrued_fee),
+                        )))
+                    }
                     _ => panic!("DO NOT ENTER HERE"),
                 },
             },
@@ -117,6 +143,9 @@ impl WasmMockQuerier {
             governance: String::from("governance"),
             treasury: String::from("treasury"),
             invested_balance: Uint128::from(0u128),
+            strategy_balances: HashMap::new(),
+            strategy_accrued_fees: HashMap::new(),
+            strategies_unavailable: false,
             tax: (Decimal::percent(1), Uint128::from(1000000u128)),
         }
     }
@@ -131,6 +160,25 @@ impl WasmMockQuerier {
         self.invested_balance = invested_balance.clone();
     }
 
+    pub fn with_strategy_balances(&mut self, balances: &[(&String, &Uint128)]) {
+        for (strategy, balance) in balances {
+            self.strategy_balances
+                .insert(strategy.to_string(), **balance);
+        }
+    }
+
+    pub fn with_strategy_accrued_fees(&mut self, accrued_fees: &[(&String, &Uint128)]) {
+        for (strategy, accrued_fee) in accrued_fees {
+            self.strategy_accrued_fees
+                .insert(strategy.to_string(), **accrued_fee);
+        }
+    }
+
+    /// Fail every strategy query
+    pub fn with_strategies_unavailable(&mut self) {
+        self.strategies_unavailable = true;
+    }
+
     pub fn compute_tax(&self, amount: Uint128) -> SystemResult<Uint128> {
         let tax = amount
             .checked_sub(amount.multiply_ratio(
@@ -1,5 +1,7 @@
 mod claim_test;
 mod contract_test;
 mod deposit_test;
+mod insurance_test;
 mod manage_test;
+mod migration_test;
 mod mock_querier;
@@ -3,8 +3,10 @@ use cosmwasm_std::{
     Response, StdError, StdResult, Uint128, WasmQuery,
 };
 
+use crate::fee::{collect_performance_fee, compute_performance_fee};
 use crate::state::{read_state, store_state, Config};
 use anchor_market::market::{
+    BorrowerInfoResponse as AnchorMarketBorrowerInfoResponse,
     ConfigResponse as AnchorMarketConfigResponse,
     EpochStateResponse as AnchorMarketEpochStateResponse, QueryMsg as AnchorMarketQueryMsg,
 };
@@ -12,7 +14,7 @@ use anchor_market::market::{
     Cw20HookMsg as AnchorMarketCw20HookMsg, ExecuteMsg as AnchorExecuteMsg,
 };
 use athena::asset::{Asset, AssetInfo};
-use athena::querier::{query_token_balance, query_treasury};
+use athena::querier::query_token_balance;
 
 pub fn query_anchor_market_config(
     deps: Deps,
@@ -27,7 +29,7 @@ pub fn query_anchor_market_config(
     Ok(anchor_market_config_response)
 }
 
-fn query_anchor_exchange_rate(
+pub fn query_anchor_exchange_rate(
     deps: Deps,
     block_height: Option<u64>,
     anchor_market: &String,
@@ -44,6 +46,21 @@ fn query_anchor_exchange_rate(
     Ok(anchor_market_epoch_state_response.exchange_rate.into())
 }
 
+/// Return the ANC rewards of the strategy pending in anchor market
+pub fn query_anchor_pending_rewards(deps: Deps, env: Env, config: &Config) -> StdResult<Uint128> {
+    let borrower_info_response: AnchorMarketBorrowerInfoResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+            msg: to_binary(&AnchorMarketQueryMsg::BorrowerInfo {
+                borrower: env.contract.address.to_string(),
+                block_height: Some(env.block.height),
+            })?,
+        }))?;
+
+    let pending_rewards: Decimal = borrower_info_response.pending_rewards.into();
+    Ok(Uint128::from(1u128) * pending_rewards)
+}
+
 pub fn get_anchor_balance(
     deps: Deps,
     config: Config,
@@ -97,7 +114,7 @@ pub fn redeem_from_anchor(
     let aterra_balance = query_token_balance(
         &deps.querier,
         deps.api.addr_humanize(&config.aterra_contract)?,
-        env.contract.address,
+        env.contract.address.clone(),
     )?;
 
     let aterra_to_redeem = if aterra_amount.is_none() {
@@ -140,27 +157,21 @@ pub fn redeem_from_anchor(
         let original_deposited =
             state.anchor_deposited * Decimal::from_ratio(aterra_to_redeem, aterra_balance);
         if original_deposited < anchor_balance {
-            let performance_fee =
-                anchor_balance.checked_sub(original_deposited)? * config.performance_fee;
+            let performance_fee = compute_performance_fee(
+                deps.as_ref(),
+                env.clone(),
+                &config,
+                &mut state,
+                anchor_balance.checked_sub(original_deposited)?,
+                Uint128::zero(),
+            )?;
 
             if !performance_fee.is_zero() {
-                let fee_asset = Asset {
-                    amount: performance_fee,
-                    info: config.get_underlying(),
-                };
-
-                messages.push(
-                    fee_asset.into_msg(
-                        &deps.querier,
-                        Addr::unchecked(
-                            query_treasury(
-                                &deps.querier,
-                                deps.api.addr_humanize(&config.controller)?,
-                            )?
-                            .to_string(),
-                        ),
-                    )?,
-                );
+                messages.extend(collect_performance_fee(
+                    deps.as_ref(),
+                    &config,
+                    performance_fee,
+                )?);
                 logs.push(attr("performance_fee", performance_fee));
             } else {
                 logs.push(attr("performance_fee", '0'));
@@ -174,11 +185,7 @@ pub fn redeem_from_anchor(
     Ok(Response::new().add_messages(messages).add_attributes(logs))
 }
 
-pub fn get_anchor_balance_without_fee(
-    deps: DepsMut,
-    env: Env,
-    config: Config,
-) -> StdResult<Uint128> {
+pub fn get_anchor_balance_without_fee(deps: Deps, env: Env, config: Config) -> StdResult<Uint128> {
     let aterra_amount = query_token_balance(
         &deps.querier,
         deps.api.addr_humanize(&config.aterra_contract)?,
@@ -186,7 +193,7 @@ pub fn get_anchor_balance_without_fee(
     )?;
     if !aterra_amount.is_zero() {
         let anchor_exchange_rate = query_anchor_exchange_rate(
-            deps.as_ref(),
+            deps,
             Some(env.block.height),
             &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
         );
@@ -8,13 +8,21 @@ use cosmwasm_std::{
 
 use crate::anchor_helper::query_anchor_market_config;
 use crate::invest::{
-    close_short_position, compound_mirror, compound_mirror_hook, deposit_anchor, deposit_mirror,
-    deposit_mirror_hook, open_short_position, withdraw_all, withdraw_anchor, withdraw_mirror,
-    withdraw_mirror_hook,
+    check_short_position, close_delta_neutral, close_delta_neutral_hook, close_short_position,
+    compound_anchor, compound_anchor_hook, compound_mirror, compound_mirror_hook, deploy,
+    deposit_anchor, deposit_mirror, deposit_mirror_hook, harvest, harvest_hook,
+    open_delta_neutral, open_short_position, realize_mirror_lp_hook, rebalance,
+    record_mirror_lp_hook, reduce_short_position, unlock_short_proceeds, withdraw_all,
+    withdraw_anchor, withdraw_invested, withdraw_invested_hook, withdraw_mirror,
+    withdraw_mirror_hook, withdraw_short_collateral, withdraw_short_collateral_hook,
 };
-use crate::manage::{update_config, withdraw_to_vault};
-use crate::querier::{query_config, query_state, query_total_balance};
-use crate::state::{store_config, store_state, Config};
+use crate::manage::{remove_short_position, update_config, withdraw_to_vault};
+use crate::mirror_helper::query_mirror_mint_config;
+use crate::querier::{
+    query_balances, query_config, query_mirror_lp_positions, query_net_delta,
+    query_net_total_balance, query_short_positions, query_state, query_total_balance,
+};
+use crate::state::{read_config, read_state, store_config, store_state, Config};
 use athena::vault_strategy::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, State};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -31,6 +39,7 @@ pub fn instantiate(
     }
 
     let anchor_market_config = query_anchor_market_config(deps.as_ref(), &msg.anchor_market);
+    let mirror_mint_config = query_mirror_mint_config(deps.as_ref(), &msg.mirror_mint);
 
     store_config(
         deps.storage,
@@ -39,14 +48,27 @@ pub fn instantiate(
             controller: deps.api.addr_canonicalize(&msg.controller)?,
             vault: deps.api.addr_canonicalize(&msg.vault)?,
             performance_fee: msg.performance_fee,
+            insurance_fee: Decimal::zero(),
+            anchor_allocation: Decimal::zero(),
+            rebalance_band: Decimal::percent(5u64),
+            max_spread: Decimal::percent(1u64),
+            slippage_tolerance: Decimal::percent(1u64),
+            oracle_price_band: Decimal::percent(5u64),
+            short_ratio_low: Decimal::percent(200u64),
+            short_ratio_high: Decimal::percent(300u64),
+            keeper_bounty: Uint128::zero(),
+            harvest_bounty: Uint128::zero(),
+            harvest_interval: 86400u64,
             stable_denom: msg.stable_denom,
             anchor_market: deps.api.addr_canonicalize(&msg.anchor_market)?,
             aterra_contract: deps
                 .api
                 .addr_canonicalize(&anchor_market_config?.aterra_contract)?,
+            anchor_token: deps.api.addr_canonicalize(&msg.anchor_token)?,
             mirror_token: deps.api.addr_canonicalize(&msg.mirror_token)?,
             mirror_staking: deps.api.addr_canonicalize(&msg.mirror_staking)?,
             mirror_mint: deps.api.addr_canonicalize(&msg.mirror_mint)?,
+            mirror_lock: deps.api.addr_canonicalize(&mirror_mint_config?.lock)?,
             mirror_oracle: deps.api.addr_canonicalize(&msg.mirror_oracle)?,
             terraswap_factory: deps.api.addr_canonicalize(&msg.terraswap_factory)?,
         },
@@ -57,6 +79,9 @@ pub fn instantiate(
         &State {
             anchor_deposited: Uint128::zero(),
             aterra_collateral: Uint128::zero(),
+            high_water_mark: Uint128::zero(),
+            vault_debt: Uint128::zero(),
+            last_harvest: 0u64,
         },
     )?;
 
@@ -69,7 +94,39 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::UpdateConfig {
             controller,
             performance_fee,
-        } => update_config(deps, info, controller, performance_fee),
+            insurance_fee,
+            anchor_allocation,
+            rebalance_band,
+            max_spread,
+            slippage_tolerance,
+            oracle_price_band,
+            short_ratio_low,
+            short_ratio_high,
+            keeper_bounty,
+            harvest_bounty,
+            harvest_interval,
+        } => update_config(
+            deps,
+            info,
+            controller,
+            performance_fee,
+            insurance_fee,
+            anchor_allocation,
+            rebalance_band,
+            max_spread,
+            slippage_tolerance,
+            oracle_price_band,
+            short_ratio_low,
+            short_ratio_high,
+            keeper_bounty,
+            harvest_bounty,
+            harvest_interval,
+        ),
+        ExecuteMsg::RemoveShortPosition { position_idx } => {
+            remove_short_position(deps, env, info, position_idx)
+        }
+        ExecuteMsg::Deploy {} => deploy(deps, env, info),
+        ExecuteMsg::Rebalance {} => rebalance(deps, env, info),
         ExecuteMsg::DepositAnchor { amount } => deposit_anchor(deps, info, amount),
         ExecuteMsg::WithdrawAnchor { aterra_amount } => {
             withdraw_anchor(deps, env, info, aterra_amount)
@@ -77,19 +134,55 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::DepositMirror {
             amount,
             mirror_asset_addr,
-        } => deposit_mirror(deps, env, info, amount, mirror_asset_addr),
+            max_spread,
+        } => deposit_mirror(deps, env, info, amount, mirror_asset_addr, max_spread),
         ExecuteMsg::DepositMirrorHook { mirror_asset_addr } => {
             deposit_mirror_hook(deps, env, info, mirror_asset_addr)
         }
+        ExecuteMsg::RecordMirrorLpHook {
+            mirror_asset_addr,
+            prev_lp_amount,
+        } => record_mirror_lp_hook(deps, env, info, mirror_asset_addr, prev_lp_amount),
         ExecuteMsg::WithdrawMirror {
             mirror_lp_amount,
             mirror_asset_addr,
-        } => withdraw_mirror(deps, env, info, mirror_lp_amount, mirror_asset_addr),
-        ExecuteMsg::WithdrawMirrorHook { mirror_asset_addr } => {
-            withdraw_mirror_hook(deps, env, info, mirror_asset_addr)
+            max_spread,
+        } => withdraw_mirror(
+            deps,
+            env,
+            info,
+            mirror_lp_amount,
+            mirror_asset_addr,
+            max_spread,
+        ),
+        ExecuteMsg::WithdrawMirrorHook {
+            mirror_asset_addr,
+            max_spread,
+        } => withdraw_mirror_hook(deps, env, info, mirror_asset_addr, max_spread),
+        ExecuteMsg::RealizeMirrorLpHook {
+            mirror_asset_addr,
+            lp_amount,
+            prev_stable_balance,
+            prev_asset_balance,
+        } => realize_mirror_lp_hook(
+            deps,
+            env,
+            info,
+            mirror_asset_addr,
+            lp_amount,
+            prev_stable_balance,
+            prev_asset_balance,
+        ),
+        ExecuteMsg::CompoundMirror { max_spread } => compound_mirror(deps, env, info, max_spread),
+        ExecuteMsg::CompoundMirrorHook { max_spread } => {
+            compound_mirror_hook(deps, env, info, max_spread)
         }
-        ExecuteMsg::CompoundMirror {} => compound_mirror(deps, env, info),
-        ExecuteMsg::CompoundMirrorHook {} => compound_mirror_hook(deps, env, info),
+        ExecuteMsg::CompoundAnchor { max_spread } => compound_anchor(deps, env, info, max_spread),
+        ExecuteMsg::CompoundAnchorHook { max_spread } => {
+            compound_anchor_hook(deps, env, info, max_spread)
+        }
+        ExecuteMsg::Harvest {} => harvest(deps, env, info),
+        ExecuteMsg::HarvestHook {} => harvest_hook(deps, env, info),
         ExecuteMsg::OpenShortPosition {
             aterra_amount,
             collateral_ratio,
@@ -98,6 +191,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             max_spread,
         } => open_short_position(
             deps,
+            env,
             info,
             aterra_amount,
             collateral_ratio,
@@ -105,10 +199,46 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             belief_price,
             max_spread,
         ),
-        ExecuteMsg::CloseShortPosition { position_idx } => {
-            close_short_position(deps, info, position_idx)
+        ExecuteMsg::CloseShortPosition {
+            position_idx,
+            max_spread,
+        } => close_short_position(deps, env, info, position_idx, max_spread),
+        ExecuteMsg::ReduceShortPosition {
+            position_idx,
+            burn_amount,
+            max_spread,
+        } => reduce_short_position(deps, info, position_idx, burn_amount, max_spread),
+        ExecuteMsg::WithdrawShortCollateral {
+            position_idx,
+            collateral_ratio,
+        } => withdraw_short_collateral(deps, env, info, position_idx, collateral_ratio),
+        ExecuteMsg::WithdrawShortCollateralHook {
+            prev_aterra_balance,
+        } => withdraw_short_collateral_hook(deps, env, info, prev_aterra_balance),
+        ExecuteMsg::OpenDeltaNeutral {
+            mirror_asset_addr,
+            amount,
+            collateral_ratio,
+        } => open_delta_neutral(deps, env, info, mirror_asset_addr, amount, collateral_ratio),
+        ExecuteMsg::CloseDeltaNeutral {
+            position_idx,
+            max_spread,
+        } => close_delta_neutral(deps, env, info, position_idx, max_spread),
+        ExecuteMsg::CloseDeltaNeutralHook {
+            position_idx,
+            max_spread,
+        } => close_delta_neutral_hook(deps, env, info, position_idx, max_spread),
+        ExecuteMsg::CheckShortPosition { position_idx } => {
+            check_short_position(deps, env, info, position_idx)
+        }
+        ExecuteMsg::UnlockShortProceeds { position_idxs } => {
+            unlock_short_proceeds(deps, env, position_idxs)
         }
         ExecuteMsg::WithdrawAll {} => withdraw_all(deps, env, info),
+        ExecuteMsg::WithdrawInvested { amount } => withdraw_invested(deps, env, info, amount),
+        ExecuteMsg::WithdrawInvestedHook { amount } => {
+            withdraw_invested_hook(deps, env, info, amount)
+        }
         ExecuteMsg::WithdrawToVault { amount } => withdraw_to_vault(deps, env, info, amount),
     }
 }
@@ -119,10 +249,24 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::TotalBalance {} => to_binary(&query_total_balance(deps, env)?),
+        QueryMsg::NetTotalBalance {} => to_binary(&query_net_total_balance(deps, env)?),
+        QueryMsg::MirrorPositions {} => to_binary(&query_mirror_lp_positions(deps)?),
+        QueryMsg::ShortPositions {} => to_binary(&query_short_positions(deps, env)?),
+        QueryMsg::NetDelta {} => to_binary(&query_net_delta(deps, env)?),
+        QueryMsg::Balances {} => to_binary(&query_balances(deps, env)?),
     }
 }
 
+/// Strategy storage of the single strategy vault cannot be migrated in place,
+/// as its short positions and mirror liquidity cost were never recorded.
+/// Such strategy must be replaced by a new instance through vault MigrateStrategy
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    if read_config(deps.storage).is_err() || read_state(deps.storage).is_err() {
+        return Err(StdError::generic_err(
+            "Legacy strategy storage, deploy a new strategy and use vault MigrateStrategy",
+        ));
+    }
+
     Ok(Response::default())
 }
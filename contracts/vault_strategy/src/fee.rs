@@ -0,0 +1,96 @@
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, Deps, Env, StdResult, Uint128, WasmMsg};
+
+use crate::querier::{get_total_balance, query_vault_debt};
+use crate::state::{read_state, Config};
+use athena::asset::Asset;
+use athena::querier::query_treasury;
+use athena::vault::ExecuteMsg as VaultExecuteMsg;
+use athena::vault_strategy::State;
+
+/// Return performance fee of the profit above the high-water mark of net gain,
+/// and raise the mark by the profit left after the fee.
+/// Net gain is the total balance above the debt to the vault,
+/// `pending_profit` is the part of the profit not included in the total balance yet
+pub fn compute_performance_fee(
+    deps: Deps,
+    env: Env,
+    config: &Config,
+    state: &mut State,
+    profit: Uint128,
+    pending_profit: Uint128,
+) -> StdResult<Uint128> {
+    let total_debt = get_vault_debt(deps, env.clone(), config, state)?;
+    state.vault_debt = total_debt;
+    let net_gain = (get_total_balance(deps, env, config.clone())? + pending_profit)
+        .saturating_sub(total_debt);
+
+    let chargeable = std::cmp::min(profit, net_gain.saturating_sub(state.high_water_mark));
+    let performance_fee = chargeable * config.performance_fee;
+    state.high_water_mark += chargeable.checked_sub(performance_fee)?;
+
+    Ok(performance_fee)
+}
+
+/// Return performance fee which would be charged on the net gain above the high-water mark
+/// if the whole `total_balance` were realized
+pub fn get_accrued_performance_fee(
+    deps: Deps,
+    env: Env,
+    config: &Config,
+    total_balance: Uint128,
+) -> StdResult<Uint128> {
+    let state = read_state(deps.storage)?;
+    let total_debt = get_vault_debt(deps, env, config, &state)?;
+    let net_gain = total_balance.saturating_sub(total_debt);
+
+    Ok(net_gain.saturating_sub(state.high_water_mark) * config.performance_fee)
+}
+
+/// Return the debt to the vault,
+/// or the last known debt if the vault no longer lists the strategy
+pub fn get_vault_debt(deps: Deps, env: Env, config: &Config, state: &State) -> StdResult<Uint128> {
+    Ok(query_vault_debt(deps, env, config.clone())?.unwrap_or(state.vault_debt))
+}
+
+/// Send performance fee to treasury,
+/// except the insurance part which funds the vault insurance reserve
+pub fn collect_performance_fee(
+    deps: Deps,
+    config: &Config,
+    performance_fee: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    let insurance_fee = performance_fee * config.insurance_fee;
+    let treasury_fee = performance_fee.checked_sub(insurance_fee)?;
+
+    if !insurance_fee.is_zero() {
+        let insurance_asset = Asset {
+            amount: insurance_fee,
+            info: config.get_underlying(),
+        };
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.vault)?.to_string(),
+            msg: to_binary(&VaultExecuteMsg::FundInsurance {})?,
+            funds: vec![insurance_asset.deduct_tax(&deps.querier)?],
+        }));
+    }
+
+    if !treasury_fee.is_zero() {
+        let treasury_asset = Asset {
+            amount: treasury_fee,
+            info: config.get_underlying(),
+        };
+
+        messages.push(treasury_asset.into_msg(
+            &deps.querier,
+            Addr::unchecked(query_treasury(
+                &deps.querier,
+                deps.api.addr_humanize(&config.controller)?,
+            )?),
+        )?);
+    }
+
+    Ok(messages)
+}
@@ -1,19 +1,33 @@
 use cosmwasm_std::{
-    attr, to_binary, Addr, Attribute, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response,
-    StdError, StdResult, Uint128, WasmMsg,
+    attr, to_binary, Addr, Attribute, CosmosMsg, Decimal, Deps, DepsMut, Env, Fraction,
+    MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 
-use crate::anchor_helper::{deposit_to_anchor, redeem_from_anchor};
+use crate::anchor_helper::{
+    deposit_to_anchor, get_anchor_balance, get_anchor_balance_without_fee,
+    query_anchor_exchange_rate, query_anchor_pending_rewards, redeem_from_anchor,
+};
+use crate::querier::get_total_balance;
 use crate::mirror_helper::{
-    query_mirror_position, query_mirror_staking_rewards, query_pair_info, reserve_simulate_swap,
-    simulate_swap,
+    assert_pool_price, burn_mirror_asset, get_lp_value, query_belief_price,
+    query_locked_short_positions, query_mirror_asset_config, query_mirror_asset_price,
+    query_mirror_bond_amount, query_mirror_position, query_mirror_staking_rewards,
+    query_next_position_idx, query_pair_info, simulate_swap_to_underlying,
+};
+use crate::fee::{collect_performance_fee, compute_performance_fee};
+use crate::state::{
+    read_config, read_harvest_bounty, read_mirror_position, read_short_position, read_state,
+    remove_harvest_bounty, remove_short_position, store_harvest_bounty, store_mirror_position,
+    store_short_position, store_state, Config, HarvestBounty, ShortPosition,
 };
-use crate::state::{read_config, read_state, store_state};
+use anchor_market::market::ExecuteMsg as AnchorExecuteMsg;
 use cw20::Cw20ExecuteMsg;
 use athena::access_control::{assert_access_privilege, assert_sender_privilege};
 use athena::asset::{Asset, AssetInfo};
-use athena::querier::query_treasury;
+use athena::querier::{query_supply, query_token_balance};
+use athena::vault::ExecuteMsg as VaultExecuteMsg;
 use athena::vault_strategy::ExecuteMsg;
+use mirror_protocol::lock::ExecuteMsg as MirrorLockExecuteMsg;
 use mirror_protocol::mint::{
     Cw20HookMsg as MirrorMintCw20HookMsg, ExecuteMsg as MirrorMintExecuteMsg, ShortParams,
 };
@@ -47,6 +61,120 @@ pub fn deposit_anchor(deps: DepsMut, info: MessageInfo, amount: Uint128) -> StdR
     ]))
 }
 
+/// Deposit idle UST to anchor money market
+/// until anchor balance reaches the anchor allocation of total balance
+pub fn deploy(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    if info.sender != env.contract.address
+        && info.sender != deps.api.addr_humanize(&config.vault)?
+    {
+        assert_access_privilege(
+            &deps.querier,
+            deps.api.addr_humanize(&config.controller)?,
+            info.sender,
+        )?;
+    }
+
+    let idle_balance = config.get_underlying().query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
+    )?;
+    let anchor_balance = get_anchor_balance(
+        deps.as_ref(),
+        config.clone(),
+        env.contract.address.clone(),
+        env.block.height,
+    )?;
+    let target_balance =
+        get_total_balance(deps.as_ref(), env, config.clone())? * config.anchor_allocation;
+
+    let amount = std::cmp::min(idle_balance, target_balance.saturating_sub(anchor_balance));
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    if !amount.is_zero() {
+        let mut state = read_state(deps.storage)?;
+        state.anchor_deposited += amount;
+        store_state(deps.storage, &state)?;
+
+        deposit_to_anchor(deps.as_ref(), config, amount, &mut messages)?;
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "deploy"),
+        attr("amount", amount),
+    ]))
+}
+
+/// Move anchor balance back to the anchor allocation of total balance
+/// when it drifts out of the rebalance band.
+/// Mirror liquidity and short positions are left to the workers
+pub fn rebalance(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_access_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        info.sender,
+    )?;
+
+    let total_balance = get_total_balance(deps.as_ref(), env.clone(), config.clone())?;
+    let anchor_balance = get_anchor_balance(
+        deps.as_ref(),
+        config.clone(),
+        env.contract.address.clone(),
+        env.block.height,
+    )?;
+    let target_balance = total_balance * config.anchor_allocation;
+    let band = total_balance * config.rebalance_band;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![attr("action", "rebalance")];
+
+    if anchor_balance + band < target_balance {
+        let idle_balance = config.get_underlying().query_balance(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?;
+        let amount = std::cmp::min(idle_balance, target_balance.checked_sub(anchor_balance)?);
+
+        if !amount.is_zero() {
+            let mut state = read_state(deps.storage)?;
+            state.anchor_deposited += amount;
+            store_state(deps.storage, &state)?;
+
+            deposit_to_anchor(deps.as_ref(), config, amount, &mut messages)?;
+            logs.push(attr("deposit_anchor", amount));
+        }
+    } else if anchor_balance > target_balance + band {
+        let aterra_balance = query_token_balance(
+            &deps.querier,
+            deps.api.addr_humanize(&config.aterra_contract)?,
+            env.contract.address.clone(),
+        )?;
+        let aterra_amount = aterra_balance.multiply_ratio(
+            anchor_balance.checked_sub(target_balance)?,
+            anchor_balance,
+        );
+
+        if !aterra_amount.is_zero() {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+                    aterra_amount: Some(aterra_amount),
+                })?,
+            }));
+            logs.push(attr("withdraw_anchor", aterra_amount));
+        }
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(logs))
+}
+
 /// Withdraw UST from anchor money market
 pub fn withdraw_anchor(
     deps: DepsMut,
@@ -75,6 +203,7 @@ pub fn deposit_mirror(
     info: MessageInfo,
     amount: Uint128,
     mirror_asset_addr: String,
+    max_spread: Option<Decimal>,
 ) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
@@ -88,6 +217,9 @@ pub fn deposit_mirror(
         return Err(StdError::generic_err("Amount must be greater than zero"));
     }
 
+    let belief_price =
+        query_belief_price(deps.as_ref(), &config, mirror_asset_addr.clone(), false)?;
+
     let mirror_asset = AssetInfo::Token {
         contract_addr: mirror_asset_addr.clone(),
     };
@@ -103,6 +235,12 @@ pub fn deposit_mirror(
 
     let half_amount: Uint128 = amount * Decimal::percent(50);
 
+    // UST swapped to mirror asset is a cost of the liquidity
+    let mirror_asset_raw = deps.api.addr_canonicalize(&mirror_asset_addr)?;
+    let mut position = read_mirror_position(deps.storage, &mirror_asset_raw)?;
+    position.cost += half_amount;
+    store_mirror_position(deps.storage, &mirror_asset_raw, &position)?;
+
     let offer_asset = Asset {
         amount: half_amount,
         info: config.get_underlying(),
@@ -119,8 +257,8 @@ pub fn deposit_mirror(
                 &deps.querier,
                 pair_info.contract_addr,
                 to_binary(&TerraPairExecuteMsg::Swap {
-                    belief_price: None,
-                    max_spread: None,
+                    belief_price: Some(belief_price),
+                    max_spread: Some(config.get_max_spread(max_spread)),
                     to: None,
                     offer_asset: offer_asset_tax_deducted.into(),
                 })?,
@@ -176,8 +314,16 @@ pub fn deposit_mirror_hook(
         Addr::unchecked(pair_info.contract_addr.to_string()),
     )?;
 
+    assert_pool_price(
+        deps.as_ref(),
+        &config,
+        mirror_asset_addr.clone(),
+        pool_stable_balance,
+        pool_mirror_balance,
+    )?;
+
     let mirror_token_amount =
-        mirror_asset_info.query_balance(&deps.querier, deps.api, env.contract.address)?;
+        mirror_asset_info.query_balance(&deps.querier, deps.api, env.contract.address.clone())?;
 
     let stable_amount_for_lp =
         mirror_token_amount * Decimal::from_ratio(pool_stable_balance, pool_mirror_balance);
@@ -189,6 +335,15 @@ pub fn deposit_mirror_hook(
 
     let tax_deducted = underlying_asset.deduct_tax(&deps.querier)?.amount;
 
+    let mirror_asset_raw = deps.api.addr_canonicalize(&mirror_asset_addr)?;
+    let mut position = read_mirror_position(deps.storage, &mirror_asset_raw)?;
+    position.cost += stable_amount_for_lp;
+    store_mirror_position(deps.storage, &mirror_asset_raw, &position)?;
+
+    // LP minted by terraswap is measured once auto stake bonds it
+    let prev_lp_amount =
+        query_mirror_bond_amount(deps.as_ref(), config.clone(), &mirror_asset_addr)?;
+
     Ok(Response::new()
         .add_messages(vec![
             CosmosMsg::Wasm(WasmMsg::Execute {
@@ -216,9 +371,17 @@ pub fn deposit_mirror_hook(
                         }
                         .into(),
                     ],
-                    slippage_tolerance: None,
+                    slippage_tolerance: Some(config.slippage_tolerance),
                 })?,
             )?,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::RecordMirrorLpHook {
+                    mirror_asset_addr,
+                    prev_lp_amount,
+                })?,
+            }),
         ])
         .add_attributes(vec![
             attr("denom_amount", tax_deducted),
@@ -226,6 +389,33 @@ pub fn deposit_mirror_hook(
         ]))
 }
 
+/// Only contract itself can execute
+/// Add the LP bonded by auto stake to the mirror position
+pub fn record_mirror_lp_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mirror_asset_addr: String,
+    prev_lp_amount: Uint128,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let lp_amount = query_mirror_bond_amount(deps.as_ref(), config, &mirror_asset_addr)?
+        .checked_sub(prev_lp_amount)?;
+
+    let mirror_asset_raw = deps.api.addr_canonicalize(&mirror_asset_addr)?;
+    let mut position = read_mirror_position(deps.storage, &mirror_asset_raw)?;
+    position.lp_amount += lp_amount;
+    store_mirror_position(deps.storage, &mirror_asset_raw, &position)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "record_mirror_lp_hook"),
+        attr("lp_amount", lp_amount),
+    ]))
+}
+
 /// Unbond mirror LP tokens from mirror staking
 /// and swap to UST
 pub fn withdraw_mirror(
@@ -234,6 +424,7 @@ pub fn withdraw_mirror(
     info: MessageInfo,
     mirror_lp_amount: Uint128,
     mirror_asset_addr: String,
+    max_spread: Option<Decimal>,
 ) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
@@ -255,8 +446,16 @@ pub fn withdraw_mirror(
             .addr_humanize(&config.terraswap_factory)?
             .to_string(),
         config.get_underlying(),
-        mirror_asset,
+        mirror_asset.clone(),
+    )?;
+
+    let prev_stable_balance = config.get_underlying().query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
     )?;
+    let prev_asset_balance =
+        mirror_asset.query_balance(&deps.querier, deps.api, env.contract.address.clone())?;
 
     Ok(Response::new()
         .add_messages(vec![
@@ -280,7 +479,20 @@ pub fn withdraw_mirror(
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: env.contract.address.to_string(),
                 funds: vec![],
-                msg: to_binary(&ExecuteMsg::WithdrawMirrorHook { mirror_asset_addr })?,
+                msg: to_binary(&ExecuteMsg::WithdrawMirrorHook {
+                    mirror_asset_addr: mirror_asset_addr.clone(),
+                    max_spread,
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::RealizeMirrorLpHook {
+                    mirror_asset_addr,
+                    lp_amount: mirror_lp_amount,
+                    prev_stable_balance,
+                    prev_asset_balance,
+                })?,
             }),
         ])
         .add_attributes(vec![
@@ -289,8 +501,71 @@ pub fn withdraw_mirror(
         ]))
 }
 
+/// Only contract itself can execute
+/// Realize the cost basis of the withdrawn liquidity at the measured proceeds,
+/// mirror asset kept to burn against a short position is valued at the oracle price
+pub fn realize_mirror_lp_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mirror_asset_addr: String,
+    lp_amount: Uint128,
+    prev_stable_balance: Uint128,
+    prev_asset_balance: Uint128,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let mirror_asset_raw = deps.api.addr_canonicalize(&mirror_asset_addr)?;
+    let mut position = read_mirror_position(deps.storage, &mirror_asset_raw)?;
+    let withdrawn_lp_amount = std::cmp::min(lp_amount, position.lp_amount);
+    if withdrawn_lp_amount.is_zero() {
+        return Ok(Response::new());
+    }
+
+    let stable_proceeds = config
+        .get_underlying()
+        .query_balance(&deps.querier, deps.api, env.contract.address.clone())?
+        .saturating_sub(prev_stable_balance);
+    let asset_amount = AssetInfo::Token {
+        contract_addr: mirror_asset_addr.clone(),
+    }
+    .query_balance(&deps.querier, deps.api, env.contract.address)?
+    .saturating_sub(prev_asset_balance);
+    let asset_proceeds = if asset_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        asset_amount
+            * query_mirror_asset_price(
+                deps.as_ref(),
+                deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+                mirror_asset_addr,
+                config.stable_denom.clone(),
+            )?
+    };
+    let proceeds = stable_proceeds + asset_proceeds;
+
+    let withdrawn_cost = position
+        .cost
+        .multiply_ratio(withdrawn_lp_amount, position.lp_amount);
+    position.cost = position.cost.checked_sub(withdrawn_cost)?;
+    position.lp_amount = position.lp_amount.checked_sub(withdrawn_lp_amount)?;
+    position.realized_cost += withdrawn_cost;
+    position.realized_proceeds += proceeds;
+    store_mirror_position(deps.storage, &mirror_asset_raw, &position)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "realize_mirror_lp_hook"),
+        attr("lp_amount", withdrawn_lp_amount),
+        attr("proceeds", proceeds),
+    ]))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn open_short_position(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     aterra_amount: Uint128,
     collateral_ratio: Decimal,
@@ -315,6 +590,20 @@ pub fn open_short_position(
         amount: aterra_amount,
     };
 
+    // Mirror mint opens the position at the next index within this transaction
+    let position_idx = query_next_position_idx(deps.as_ref(), &config)?;
+    store_short_position(
+        deps.storage,
+        position_idx,
+        &ShortPosition {
+            mirror_asset: deps.api.addr_canonicalize(&mirror_asset_addr)?,
+            collateral: aterra_amount,
+            collateral_ratio,
+            opened_at: env.block.time.seconds(),
+            proceeds_locked: true,
+        },
+    )?;
+
     let mirror_asset_info = AssetInfo::Token {
         contract_addr: mirror_asset_addr,
     };
@@ -338,6 +627,7 @@ pub fn open_short_position(
         )?])
         .add_attributes(vec![
             attr("action", "open_short_position"),
+            attr("position_idx", position_idx),
             attr("aterra_amount", aterra_amount),
             attr("collateral_ratio", collateral_ratio.to_string()),
         ]))
@@ -345,8 +635,10 @@ pub fn open_short_position(
 
 pub fn close_short_position(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     position_idx: Uint128,
+    max_spread: Option<Decimal>,
 ) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
@@ -366,139 +658,773 @@ pub fn close_short_position(
         return Err(StdError::generic_err("Already closed"));
     }
 
-    let burn_asset: Asset = position.asset.into();
+    remove_short_position(deps.storage, position_idx);
 
-    let pair_info = query_pair_info(
+    let mut messages: Vec<CosmosMsg> = vec![];
+    burn_mirror_asset(
         deps.as_ref(),
-        deps.api
-            .addr_humanize(&config.terraswap_factory)?
-            .to_string(),
-        config.get_underlying(),
-        burn_asset.clone().info,
+        &config,
+        position.asset.into(),
+        position_idx,
+        max_spread,
+        &mut messages,
     )?;
-
-    let reserve_simulation = reserve_simulate_swap(
+    withdraw_short_collateral_messages(
         deps.as_ref(),
-        burn_asset.clone(),
-        pair_info.clone().contract_addr,
+        env,
+        &config,
+        position_idx,
+        None,
+        &mut messages,
     )?;
 
-    let offer_asset = Asset {
-        info: config.get_underlying(),
-        amount: reserve_simulation.offer_amount,
-    };
-
-    let mut state = read_state(deps.storage)?;
-    state.aterra_collateral = state
-        .aterra_collateral
-        .checked_sub(position.collateral.amount)?;
-    store_state(deps.storage, &state)?;
-
     Ok(Response::new()
-        .add_messages(vec![
-            offer_asset.into_msg_with_data(
-                &deps.querier,
-                pair_info.contract_addr,
-                to_binary(&TerraswapCw20HookMsg::Swap {
-                    belief_price: None,
-                    max_spread: None,
-                    to: None,
-                })?,
-            )?,
-            burn_asset.into_msg_with_data(
-                &deps.querier,
-                deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
-                to_binary(&MirrorMintCw20HookMsg::Burn { position_idx })?,
-            )?,
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
-                funds: vec![],
-                msg: to_binary(&MirrorMintExecuteMsg::Withdraw {
-                    position_idx,
-                    collateral: None,
-                })?,
-            }),
-        ])
+        .add_messages(messages)
         .add_attributes(vec![
             attr("action", "close_short_position"),
             attr("position_idx", position_idx),
         ]))
 }
 
-/// Only contract itself can execute
-/// Swap LP to UST
-pub fn withdraw_mirror_hook(
+/// Burn part of the minted asset of a short position
+pub fn reduce_short_position(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    mirror_asset_addr: String,
+    position_idx: Uint128,
+    burn_amount: Uint128,
+    max_spread: Option<Decimal>,
 ) -> StdResult<Response> {
-    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
-
     let config = read_config(deps.storage)?;
 
-    let mirror_asset_info = AssetInfo::Token {
-        contract_addr: mirror_asset_addr,
-    };
+    assert_access_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        info.sender,
+    )?;
 
-    let pair_info = query_pair_info(
+    let position = query_mirror_position(deps.as_ref(), config.clone(), position_idx)?;
+
+    if !position.is_short {
+        return Err(StdError::generic_err("Not short position"));
+    }
+
+    if burn_amount.is_zero() || burn_amount >= position.asset.amount {
+        return Err(StdError::generic_err(
+            "Burn amount must be less than minted amount",
+        ));
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    burn_mirror_asset(
         deps.as_ref(),
-        deps.api
-            .addr_humanize(&config.terraswap_factory)?
-            .to_string(),
-        config.get_underlying(),
-        mirror_asset_info.clone(),
+        &config,
+        Asset {
+            info: position.asset.info.into(),
+            amount: burn_amount,
+        },
+        position_idx,
+        max_spread,
+        &mut messages,
     )?;
 
-    let mirror_asset_balance = mirror_asset_info.query_balance(
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "reduce_short_position"),
+            attr("position_idx", position_idx),
+            attr("burn_amount", burn_amount),
+        ]))
+}
+
+/// Withdraw collateral of a short position above the given collateral ratio
+pub fn withdraw_short_collateral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_idx: Uint128,
+    collateral_ratio: Decimal,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_access_privilege(
         &deps.querier,
-        deps.api,
-        Addr::unchecked(env.contract.address.to_string()),
+        deps.api.addr_humanize(&config.controller)?,
+        info.sender,
     )?;
 
-    let mirror_asset = Asset {
-        info: mirror_asset_info,
-        amount: mirror_asset_balance,
-    };
+    let position = query_mirror_position(deps.as_ref(), config.clone(), position_idx)?;
 
-    if mirror_asset_balance.is_zero() {
-        Ok(Response::new())
-    } else {
-        Ok(Response::new().add_message(mirror_asset.into_msg_with_data(
-            &deps.querier,
-            pair_info.contract_addr.clone(),
-            to_binary(&TerraswapCw20HookMsg::Swap {
-                belief_price: None,
-                max_spread: None,
-                to: None,
-            })?,
-        )?))
+    if !position.is_short {
+        return Err(StdError::generic_err("Not short position"));
     }
-}
 
-/// Withdraw invested UST from anchor and mirror protocol
-pub fn withdraw_all(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
-    let config = read_config(deps.storage)?;
+    let mirror_asset = position.asset.info.to_string();
+    let asset_config = query_mirror_asset_config(deps.as_ref(), &config, mirror_asset.clone())?;
+    if collateral_ratio < asset_config.min_collateral_ratio {
+        return Err(StdError::generic_err(
+            "Collateral ratio must be greater than min collateral ratio",
+        ));
+    }
 
-    if info.sender != env.contract.address
-        && info.sender != deps.api.addr_humanize(&config.clone().vault)?
-    {
-        assert_access_privilege(
-            &deps.querier,
-            deps.api.addr_humanize(&config.controller)?,
-            info.sender,
-        )?;
+    let price = query_mirror_asset_price(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+        mirror_asset,
+        config.stable_denom.clone(),
+    )?;
+    let exchange_rate = query_anchor_exchange_rate(
+        deps.as_ref(),
+        Some(env.block.height),
+        &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+    )?;
+    if exchange_rate.is_zero() {
+        return Err(StdError::generic_err("Invalid anchor exchange rate"));
     }
 
-    let reward_infos = query_mirror_staking_rewards(deps.as_ref(), config.clone())?;
+    let required_aterra = (position.asset.amount * price * collateral_ratio)
+        .multiply_ratio(exchange_rate.denominator(), exchange_rate.numerator());
+    let withdraw_amount = position
+        .collateral
+        .amount
+        .saturating_sub(required_aterra);
+    if withdraw_amount.is_zero() {
+        return Err(StdError::generic_err("No surplus collateral"));
+    }
 
     let mut messages: Vec<CosmosMsg> = vec![];
+    withdraw_short_collateral_messages(
+        deps.as_ref(),
+        env,
+        &config,
+        position_idx,
+        Some(Asset {
+            info: position.collateral.info.into(),
+            amount: withdraw_amount,
+        }),
+        &mut messages,
+    )?;
 
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::WithdrawAnchor {
-            aterra_amount: None,
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "withdraw_short_collateral"),
+            attr("position_idx", position_idx),
+            attr("amount", withdraw_amount),
+        ]))
+}
+
+/// Withdraw collateral from mirror mint, then account the aUST actually returned
+fn withdraw_short_collateral_messages(
+    deps: Deps,
+    env: Env,
+    config: &Config,
+    position_idx: Uint128,
+    collateral: Option<Asset>,
+    messages: &mut Vec<CosmosMsg>,
+) -> StdResult<()> {
+    let aterra_balance = query_token_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.aterra_contract)?,
+        env.contract.address.clone(),
+    )?;
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+        funds: vec![],
+        msg: to_binary(&MirrorMintExecuteMsg::Withdraw {
+            position_idx,
+            collateral: collateral.map(|collateral| collateral.into()),
+        })?,
+    }));
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::WithdrawShortCollateralHook {
+            prev_aterra_balance: aterra_balance,
+        })?,
+    }));
+
+    Ok(())
+}
+
+/// Only contract itself can execute
+/// Reduce aUST collateral by the aUST returned from mirror mint
+pub fn withdraw_short_collateral_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    prev_aterra_balance: Uint128,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let aterra_balance = query_token_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.aterra_contract)?,
+        env.contract.address,
+    )?;
+    let returned_amount = aterra_balance.checked_sub(prev_aterra_balance)?;
+
+    let mut state = read_state(deps.storage)?;
+    state.aterra_collateral = state.aterra_collateral.saturating_sub(returned_amount);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "withdraw_short_collateral_hook"),
+        attr("returned_amount", returned_amount),
+    ]))
+}
+
+/// Anyone can execute
+/// Bring a short position below the target band back to the middle of the band
+/// Swaps always use the default max spread, as the caller is paid a bounty
+pub fn check_short_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_idx: Uint128,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    let mut short_position = match read_short_position(deps.storage, position_idx)? {
+        Some(short_position) => short_position,
+        None => return Err(StdError::generic_err("Short position not found")),
+    };
+
+    let position = query_mirror_position(deps.as_ref(), config.clone(), position_idx)?;
+    let minted_asset: Asset = position.asset.into();
+
+    let price = query_mirror_asset_price(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+        minted_asset.info.to_string(),
+        config.stable_denom.clone(),
+    )?;
+    let exchange_rate = query_anchor_exchange_rate(
+        deps.as_ref(),
+        Some(env.block.height),
+        &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+    )?;
+    if price.is_zero() || exchange_rate.is_zero() {
+        return Err(StdError::generic_err("Invalid oracle price"));
+    }
+
+    let asset_value = minted_asset.amount * price;
+    let collateral_value = position.collateral.amount * exchange_rate;
+    if asset_value.is_zero() || collateral_value >= asset_value * config.short_ratio_low {
+        return Err(StdError::generic_err("Short position is healthy"));
+    }
+
+    let target_ratio = (config.short_ratio_low + config.short_ratio_high) / Uint128::from(2u128);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attributes: Vec<Attribute> = vec![
+        attr("action", "check_short_position"),
+        attr("position_idx", position_idx),
+    ];
+
+    let required_aterra = (asset_value * target_ratio)
+        .checked_sub(collateral_value)?
+        .multiply_ratio(exchange_rate.denominator(), exchange_rate.numerator());
+    let aterra_balance = query_token_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.aterra_contract)?,
+        env.contract.address.clone(),
+    )?;
+
+    if aterra_balance >= required_aterra {
+        if required_aterra.is_zero() {
+            return Err(StdError::generic_err("Short position is healthy"));
+        }
+
+        let mut state = read_state(deps.storage)?;
+        state.aterra_collateral += required_aterra;
+        // The collateral leaves anchor deposits with its share of the deposited amount
+        let moved_deposited =
+            state.anchor_deposited * Decimal::from_ratio(required_aterra, aterra_balance);
+        state.anchor_deposited = state.anchor_deposited.checked_sub(moved_deposited)?;
+        store_state(deps.storage, &state)?;
+
+        short_position.collateral += required_aterra;
+        store_short_position(deps.storage, position_idx, &short_position)?;
+
+        messages.push(
+            Asset {
+                info: config.get_aterra_asset_info(deps.api)?,
+                amount: required_aterra,
+            }
+            .into_msg_with_data(
+                &deps.querier,
+                deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+                to_binary(&MirrorMintCw20HookMsg::Deposit { position_idx })?,
+            )?,
+        );
+        attributes.push(attr("deposit_collateral", required_aterra));
+    } else {
+        // Burn the minted asset above what the collateral covers at the target ratio
+        let burn_value = asset_value.checked_sub(
+            collateral_value.multiply_ratio(target_ratio.denominator(), target_ratio.numerator()),
+        )?;
+        let burn_asset = Asset {
+            info: minted_asset.info.clone(),
+            amount: burn_value.multiply_ratio(price.denominator(), price.numerator()),
+        };
+        if burn_asset.amount.is_zero() {
+            return Err(StdError::generic_err("Short position is healthy"));
+        }
+
+        attributes.push(attr("burn_amount", burn_asset.amount));
+        burn_mirror_asset(
+            deps.as_ref(),
+            &config,
+            burn_asset,
+            position_idx,
+            None,
+            &mut messages,
+        )?;
+    }
+
+    if !config.keeper_bounty.is_zero() {
+        messages.push(
+            Asset {
+                info: config.get_underlying(),
+                amount: config.keeper_bounty,
+            }
+            .into_msg(&deps.querier, info.sender)?,
+        );
+        attributes.push(attr("keeper_bounty", config.keeper_bounty));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// Claim the short sale proceeds of the positions whose mirror lockup period is over
+pub fn unlock_short_proceeds(
+    deps: DepsMut,
+    env: Env,
+    position_idxs: Vec<Uint128>,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    for position_idx in position_idxs.iter() {
+        if read_short_position(deps.storage, *position_idx)?.is_none() {
+            return Err(StdError::generic_err("Short position not found"));
+        }
+    }
+
+    let mut unlock_idxs: Vec<Uint128> = vec![];
+    let mut unlock_amount = Uint128::zero();
+    for (position_idx, lock_info) in
+        query_locked_short_positions(deps.as_ref(), env.clone(), config.clone())?
+    {
+        if !position_idxs.contains(&position_idx)
+            || lock_info.unlock_time > env.block.time.seconds()
+        {
+            continue;
+        }
+
+        let mut short_position = read_short_position(deps.storage, position_idx)?.unwrap();
+        short_position.proceeds_locked = false;
+        store_short_position(deps.storage, position_idx, &short_position)?;
+
+        unlock_idxs.push(position_idx);
+        unlock_amount += lock_info.locked_amount;
+    }
+
+    if unlock_idxs.is_empty() {
+        return Err(StdError::generic_err("No unlockable proceeds"));
+    }
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.mirror_lock)?.to_string(),
+            msg: to_binary(&MirrorLockExecuteMsg::UnlockPositionFunds {
+                positions_idx: unlock_idxs.clone(),
+            })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "unlock_short_proceeds"),
+            attr(
+                "position_idxs",
+                unlock_idxs
+                    .iter()
+                    .map(|idx| idx.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+            attr("unlock_amount", unlock_amount),
+        ]))
+}
+
+/// Mint the mirror asset with aUST collateral and provide it to mirror liquidity
+/// with `amount` of UST, so that the liquidity is hedged by the minted debt
+pub fn open_delta_neutral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mirror_asset_addr: String,
+    amount: Uint128,
+    collateral_ratio: Decimal,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_access_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        info.sender,
+    )?;
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Amount must be greater than 0"));
+    }
+
+    let asset_config =
+        query_mirror_asset_config(deps.as_ref(), &config, mirror_asset_addr.clone())?;
+    if collateral_ratio < asset_config.min_collateral_ratio {
+        return Err(StdError::generic_err(
+            "Collateral ratio must be greater than min collateral ratio",
+        ));
+    }
+
+    let exchange_rate = query_anchor_exchange_rate(
+        deps.as_ref(),
+        Some(env.block.height),
+        &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+    )?;
+    if exchange_rate.is_zero() {
+        return Err(StdError::generic_err("Invalid anchor exchange rate"));
+    }
+
+    // Minted asset is worth `amount` at the collateral ratio
+    let aterra_amount = (amount * collateral_ratio)
+        .multiply_ratio(exchange_rate.denominator(), exchange_rate.numerator());
+    let aterra_balance = query_token_balance(
+        &deps.querier,
+        deps.api.addr_humanize(&config.aterra_contract)?,
+        env.contract.address.clone(),
+    )?;
+    if aterra_amount > aterra_balance {
+        return Err(StdError::generic_err("Not enough aUST for collateral"));
+    }
+
+    let position_idx = query_next_position_idx(deps.as_ref(), &config)?;
+    store_short_position(
+        deps.storage,
+        position_idx,
+        &ShortPosition {
+            mirror_asset: deps.api.addr_canonicalize(&mirror_asset_addr)?,
+            collateral: aterra_amount,
+            collateral_ratio,
+            opened_at: env.block.time.seconds(),
+            proceeds_locked: false,
+        },
+    )?;
+
+    let mut state = read_state(deps.storage)?;
+    state.aterra_collateral += aterra_amount;
+    // Minting collateral is no longer an anchor deposit
+    let moved_deposited =
+        state.anchor_deposited * Decimal::from_ratio(aterra_amount, aterra_balance);
+    state.anchor_deposited = state.anchor_deposited.checked_sub(moved_deposited)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_messages(vec![
+            Asset {
+                info: config.get_aterra_asset_info(deps.api)?,
+                amount: aterra_amount,
+            }
+            .into_msg_with_data(
+                &deps.querier,
+                deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+                to_binary(&MirrorMintCw20HookMsg::OpenPosition {
+                    asset_info: AssetInfo::Token {
+                        contract_addr: mirror_asset_addr.clone(),
+                    }
+                    .into(),
+                    collateral_ratio,
+                    short_params: None,
+                })?,
+            )?,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::DepositMirrorHook { mirror_asset_addr })?,
+            }),
+        ])
+        .add_attributes(vec![
+            attr("action", "open_delta_neutral"),
+            attr("position_idx", position_idx),
+            attr("aterra_amount", aterra_amount),
+            attr("amount", amount),
+        ]))
+}
+
+/// Withdraw the mirror liquidity hedged by a delta neutral position,
+/// then repay the minted debt and withdraw the collateral
+pub fn close_delta_neutral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_idx: Uint128,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_access_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        info.sender,
+    )?;
+
+    if read_short_position(deps.storage, position_idx)?.is_none() {
+        return Err(StdError::generic_err("Short position not found"));
+    }
+
+    let position = query_mirror_position(deps.as_ref(), config.clone(), position_idx)?;
+    let minted_asset: Asset = position.asset.into();
+    let mirror_asset_addr = minted_asset.info.to_string();
+
+    let pair_info = query_pair_info(
+        deps.as_ref(),
+        deps.api
+            .addr_humanize(&config.terraswap_factory)?
+            .to_string(),
+        config.get_underlying(),
+        minted_asset.info.clone(),
+    )?;
+    let pool_mirror_balance = minted_asset.info.query_balance(
+        &deps.querier,
+        deps.api,
+        Addr::unchecked(pair_info.contract_addr.to_string()),
+    )?;
+    let lp_supply = query_supply(
+        &deps.querier,
+        Addr::unchecked(pair_info.liquidity_token.to_string()),
+    )?;
+    let bond_amount = query_mirror_bond_amount(deps.as_ref(), config.clone(), &mirror_asset_addr)?;
+
+    // Liquidity holding as much mirror asset as the minted debt
+    let lp_amount = std::cmp::min(
+        minted_asset
+            .amount
+            .multiply_ratio(lp_supply, pool_mirror_balance),
+        bond_amount,
+    );
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !lp_amount.is_zero() {
+        let prev_stable_balance = config.get_underlying().query_balance(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?;
+        let prev_asset_balance = minted_asset.info.query_balance(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?;
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.mirror_staking)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&MirrorStakingExecuteMsg::Unbond {
+                asset_token: mirror_asset_addr.clone(),
+                amount: lp_amount,
+            })?,
+        }));
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair_info.liquidity_token,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                amount: lp_amount,
+                contract: pair_info.contract_addr,
+                msg: to_binary(&TerraswapCw20HookMsg::WithdrawLiquidity {})?,
+            })?,
+        }));
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::RealizeMirrorLpHook {
+                mirror_asset_addr,
+                lp_amount,
+                prev_stable_balance,
+                prev_asset_balance,
+            })?,
+        }));
+    }
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::CloseDeltaNeutralHook {
+            position_idx,
+            max_spread,
+        })?,
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "close_delta_neutral"),
+            attr("position_idx", position_idx),
+            attr("lp_amount", lp_amount),
+        ]))
+}
+
+/// Only contract itself can execute
+/// Burn the minted debt with the withdrawn mirror asset, buying any shortfall,
+/// and withdraw all collateral
+pub fn close_delta_neutral_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_idx: Uint128,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let position = query_mirror_position(deps.as_ref(), config.clone(), position_idx)?;
+    let minted_asset: Asset = position.asset.into();
+
+    let mirror_balance = minted_asset.info.query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
+    )?;
+    let burn_amount = std::cmp::min(mirror_balance, minted_asset.amount);
+    let shortfall = minted_asset.amount.checked_sub(burn_amount)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !burn_amount.is_zero() {
+        messages.push(
+            Asset {
+                info: minted_asset.info.clone(),
+                amount: burn_amount,
+            }
+            .into_msg_with_data(
+                &deps.querier,
+                deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+                to_binary(&MirrorMintCw20HookMsg::Burn { position_idx })?,
+            )?,
+        );
+    }
+    if !shortfall.is_zero() {
+        burn_mirror_asset(
+            deps.as_ref(),
+            &config,
+            Asset {
+                info: minted_asset.info,
+                amount: shortfall,
+            },
+            position_idx,
+            max_spread,
+            &mut messages,
+        )?;
+    }
+
+    remove_short_position(deps.storage, position_idx);
+    withdraw_short_collateral_messages(
+        deps.as_ref(),
+        env,
+        &config,
+        position_idx,
+        None,
+        &mut messages,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "close_delta_neutral_hook"),
+            attr("burn_amount", burn_amount),
+            attr("shortfall", shortfall),
+        ]))
+}
+
+/// Only contract itself can execute
+/// Swap LP to UST
+pub fn withdraw_mirror_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mirror_asset_addr: String,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let belief_price =
+        query_belief_price(deps.as_ref(), &config, mirror_asset_addr.clone(), true)?;
+
+    let mirror_asset_info = AssetInfo::Token {
+        contract_addr: mirror_asset_addr,
+    };
+
+    let pair_info = query_pair_info(
+        deps.as_ref(),
+        deps.api
+            .addr_humanize(&config.terraswap_factory)?
+            .to_string(),
+        config.get_underlying(),
+        mirror_asset_info.clone(),
+    )?;
+
+    let mirror_asset_balance = mirror_asset_info.query_balance(
+        &deps.querier,
+        deps.api,
+        Addr::unchecked(env.contract.address.to_string()),
+    )?;
+
+    let mirror_asset = Asset {
+        info: mirror_asset_info,
+        amount: mirror_asset_balance,
+    };
+
+    if mirror_asset_balance.is_zero() {
+        Ok(Response::new())
+    } else {
+        Ok(Response::new().add_message(mirror_asset.into_msg_with_data(
+            &deps.querier,
+            pair_info.contract_addr.clone(),
+            to_binary(&TerraswapCw20HookMsg::Swap {
+                belief_price: Some(belief_price),
+                max_spread: Some(config.get_max_spread(max_spread)),
+                to: None,
+            })?,
+        )?))
+    }
+}
+
+/// Withdraw invested UST from anchor and mirror protocol
+pub fn withdraw_all(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    if info.sender != env.contract.address
+        && info.sender != deps.api.addr_humanize(&config.clone().vault)?
+    {
+        assert_access_privilege(
+            &deps.querier,
+            deps.api.addr_humanize(&config.controller)?,
+            info.sender,
+        )?;
+    }
+
+    let reward_infos = query_mirror_staking_rewards(deps.as_ref(), config.clone())?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+            aterra_amount: None,
         })?,
     }));
 
@@ -510,6 +1436,7 @@ pub fn withdraw_all(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Res
                 msg: to_binary(&ExecuteMsg::WithdrawMirror {
                     mirror_lp_amount: reward_info.bond_amount,
                     mirror_asset_addr: reward_info.asset_token,
+                    max_spread: None,
                 })?,
             }));
         }
@@ -518,7 +1445,7 @@ pub fn withdraw_all(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Res
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: env.contract.address.to_string(),
         funds: vec![],
-        msg: to_binary(&ExecuteMsg::CompoundMirror {})?,
+        msg: to_binary(&ExecuteMsg::CompoundMirror { max_spread: None })?,
     }));
 
     Ok(Response::new()
@@ -526,8 +1453,189 @@ pub fn withdraw_all(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Res
         .add_attribute("action", "withdraw_all"))
 }
 
-/// Withdraw MIR reward from mirror staking and swap to UST
-pub fn compound_mirror(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+/// Free `amount` of UST by redeeming aUST first, then unwinding mirror liquidity
+/// proportionally across assets, and send it to the vault
+pub fn withdraw_invested(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    if info.sender != env.contract.address
+        && info.sender != deps.api.addr_humanize(&config.vault)?
+    {
+        assert_access_privilege(
+            &deps.querier,
+            deps.api.addr_humanize(&config.controller)?,
+            info.sender,
+        )?;
+    }
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Amount must be greater than zero"));
+    }
+
+    let idle_balance = config.get_underlying().query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
+    )?;
+    let mut remaining = amount.saturating_sub(idle_balance);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attributes: Vec<Attribute> = vec![
+        attr("action", "withdraw_invested"),
+        attr("amount", amount),
+    ];
+
+    // Redeemed aUST is worth its value net of the performance fee charged on it
+    let anchor_balance = if remaining.is_zero() {
+        Uint128::zero()
+    } else {
+        get_anchor_balance_without_fee(deps.as_ref(), env.clone(), config.clone())?
+    };
+    if !anchor_balance.is_zero() {
+        let aterra_balance = query_token_balance(
+            &deps.querier,
+            deps.api.addr_humanize(&config.aterra_contract)?,
+            env.contract.address.clone(),
+        )?;
+        let aterra_amount = std::cmp::min(
+            multiply_ratio_ceil(remaining, aterra_balance, anchor_balance),
+            aterra_balance,
+        );
+        remaining =
+            remaining.saturating_sub(aterra_amount.multiply_ratio(anchor_balance, aterra_balance));
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+                aterra_amount: Some(aterra_amount),
+            })?,
+        }));
+        attributes.push(attr("aterra_amount", aterra_amount));
+    }
+
+    if !remaining.is_zero() {
+        // (mirror asset, bonded LP amount, LP value)
+        let mut lp_positions: Vec<(String, Uint128, Uint128)> = vec![];
+        let mut mirror_balance = Uint128::zero();
+        for reward_info in query_mirror_staking_rewards(deps.as_ref(), config.clone())? {
+            if reward_info.bond_amount.is_zero() || reward_info.is_short {
+                continue;
+            }
+
+            let lp_value = get_lp_value(
+                deps.as_ref(),
+                deps.api
+                    .addr_humanize(&config.terraswap_factory)?
+                    .to_string(),
+                config.get_underlying(),
+                AssetInfo::Token {
+                    contract_addr: reward_info.asset_token.clone(),
+                },
+                reward_info.bond_amount,
+            )?;
+            mirror_balance += lp_value;
+            lp_positions.push((reward_info.asset_token, reward_info.bond_amount, lp_value));
+        }
+
+        if mirror_balance < remaining {
+            return Err(StdError::generic_err("Not enough invested balance"));
+        }
+
+        for (mirror_asset_addr, bond_amount, lp_value) in lp_positions {
+            if lp_value.is_zero() {
+                continue;
+            }
+
+            let mirror_lp_amount = std::cmp::min(
+                multiply_ratio_ceil(bond_amount, remaining, mirror_balance),
+                bond_amount,
+            );
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                    mirror_lp_amount,
+                    mirror_asset_addr,
+                    max_spread: None,
+                })?,
+            }));
+        }
+    }
+
+    // Redemption tax and swap slippage can return less than sized,
+    // so the amount sent after unwinding is capped at the idle balance
+    let withdraw_msg = if messages.is_empty() {
+        ExecuteMsg::WithdrawToVault {
+            amount: Some(amount),
+        }
+    } else {
+        ExecuteMsg::WithdrawInvestedHook { amount }
+    };
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&withdraw_msg)?,
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+pub fn withdraw_invested_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+    let idle_balance = config.get_underlying().query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
+    )?;
+    let withdraw_amount = std::cmp::min(amount, idle_balance);
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::WithdrawToVault {
+                amount: Some(withdraw_amount),
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "withdraw_invested_hook"),
+            attr("amount", amount),
+            attr("withdraw_amount", withdraw_amount),
+        ]))
+}
+
+/// Multiply by the ratio rounding up, so that withdrawals free at least the ratio
+fn multiply_ratio_ceil(value: Uint128, numerator: Uint128, denominator: Uint128) -> Uint128 {
+    let floor = value.multiply_ratio(numerator, denominator);
+    if floor.multiply_ratio(denominator, numerator) < value {
+        floor + Uint128::from(1u128)
+    } else {
+        floor
+    }
+}
+
+/// Withdraw MIR reward of liquidity and short positions from mirror staking and swap to UST
+pub fn compound_mirror(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
     if info.sender != env.contract.address {
@@ -538,6 +1646,18 @@ pub fn compound_mirror(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<
         )?;
     }
 
+    // Withdraw without asset token claims both liquidity and short rewards
+    let short_reward: Uint128 = query_mirror_staking_rewards(deps.as_ref(), config.clone())?
+        .into_iter()
+        .filter(|reward_info| reward_info.is_short)
+        .map(|reward_info| reward_info.pending_reward)
+        .sum();
+
+    let mut attributes = vec![attr("action", "compound_mirror")];
+    if !short_reward.is_zero() {
+        attributes.push(attr("short_reward", short_reward));
+    }
+
     Ok(Response::new()
         .add_messages(vec![
             CosmosMsg::Wasm(WasmMsg::Execute {
@@ -548,15 +1668,20 @@ pub fn compound_mirror(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: env.contract.address.to_string(),
                 funds: vec![],
-                msg: to_binary(&ExecuteMsg::CompoundMirrorHook {})?,
+                msg: to_binary(&ExecuteMsg::CompoundMirrorHook { max_spread })?,
             }),
         ])
-        .add_attribute("action", "compound_mirror"))
+        .add_attributes(attributes))
 }
 
 /// Only contract itself can execute
 /// Swap MIR to UST
-pub fn compound_mirror_hook(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+pub fn compound_mirror_hook(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
     assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
 
     let config = read_config(deps.storage)?;
@@ -589,44 +1714,320 @@ pub fn compound_mirror_hook(deps: DepsMut, env: Env, info: MessageInfo) -> StdRe
     };
 
     if !mirror_token_balance.is_zero() {
+        // MIR has no mirror oracle feed, so the swap is bounded by max spread only
         messages.push(mirror_token_asset.clone().into_msg_with_data(
             &deps.querier,
             pair_info.contract_addr.clone(),
             to_binary(&TerraswapCw20HookMsg::Swap {
                 belief_price: None,
-                max_spread: None,
+                max_spread: Some(config.get_max_spread(max_spread)),
                 to: None,
             })?,
         )?);
 
-        let profit_amount = simulate_swap(
+        let profit_amount = simulate_swap_to_underlying(
             deps.as_ref(),
+            &config,
             mirror_token_asset.clone(),
             pair_info.contract_addr,
         )?;
 
-        let performance_fee = profit_amount * config.performance_fee;
+        let mut state = read_state(deps.storage)?;
+        let performance_fee = compute_performance_fee(
+            deps.as_ref(),
+            env.clone(),
+            &config,
+            &mut state,
+            profit_amount,
+            profit_amount,
+        )?;
+        store_state(deps.storage, &state)?;
 
         if !performance_fee.is_zero() {
-            let fee_asset = Asset {
-                amount: performance_fee,
-                info: config.get_underlying(),
-            };
-
-            messages.push(
-                fee_asset.into_msg(
-                    &deps.querier,
-                    Addr::unchecked(
-                        query_treasury(&deps.querier, deps.api.addr_humanize(&config.controller)?)?
-                            .to_string(),
-                    ),
-                )?,
-            );
+            messages.extend(collect_performance_fee(
+                deps.as_ref(),
+                &config,
+                performance_fee,
+            )?);
             logs.push(attr("performance_fee", performance_fee));
         } else {
             logs.push(attr("performance_fee", '0'));
         }
+
+        let harvest_bounty = pay_harvest_bounty(
+            deps.branch(),
+            &config,
+            profit_amount.checked_sub(performance_fee)?,
+            &mut messages,
+            &mut logs,
+        )?;
+
+        // Harvested profit stays in strategy, only the gain is reported
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.vault)?.to_string(),
+            msg: to_binary(&VaultExecuteMsg::Report {
+                gain: profit_amount.checked_sub(performance_fee + harvest_bounty)?,
+                loss: Uint128::zero(),
+                debt_payment: Uint128::zero(),
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(logs))
+}
+
+/// Claim ANC reward from anchor money market and swap to UST
+pub fn compound_anchor(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    if info.sender != env.contract.address {
+        assert_access_privilege(
+            &deps.querier,
+            deps.api.addr_humanize(&config.controller)?,
+            info.sender,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_messages(vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+                funds: vec![],
+                msg: to_binary(&AnchorExecuteMsg::ClaimRewards { to: None })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::CompoundAnchorHook { max_spread })?,
+            }),
+        ])
+        .add_attribute("action", "compound_anchor"))
+}
+
+/// Only contract itself can execute
+/// Swap ANC to UST, charge performance fee and deposit the rest to anchor
+pub fn compound_anchor_hook(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_spread: Option<Decimal>,
+) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    let config = read_config(deps.storage)?;
+
+    let anchor_token_info = AssetInfo::Token {
+        contract_addr: deps.api.addr_humanize(&config.anchor_token)?.to_string(),
+    };
+    let anchor_token_balance = anchor_token_info.query_balance(
+        &deps.querier,
+        deps.api,
+        env.contract.address.clone(),
+    )?;
+
+    if anchor_token_balance.is_zero() {
+        return Ok(Response::new());
+    }
+
+    let pair_info = query_pair_info(
+        deps.as_ref(),
+        deps.api
+            .addr_humanize(&config.terraswap_factory)?
+            .to_string(),
+        config.get_underlying(),
+        anchor_token_info.clone(),
+    )?;
+
+    let anchor_token_asset = Asset {
+        info: anchor_token_info,
+        amount: anchor_token_balance,
+    };
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs: Vec<Attribute> = vec![attr("anchor_token_amount", anchor_token_balance)];
+
+    // ANC has no oracle price, the swap is bounded by the max spread from the pool price
+    messages.push(anchor_token_asset.clone().into_msg_with_data(
+        &deps.querier,
+        pair_info.contract_addr.clone(),
+        to_binary(&TerraswapCw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(config.get_max_spread(max_spread)),
+            to: None,
+        })?,
+    )?);
+
+    let profit_amount = simulate_swap_to_underlying(
+        deps.as_ref(),
+        &config,
+        anchor_token_asset,
+        pair_info.contract_addr,
+    )?;
+
+    let mut state = read_state(deps.storage)?;
+    let performance_fee = compute_performance_fee(
+        deps.as_ref(),
+        env,
+        &config,
+        &mut state,
+        profit_amount,
+        profit_amount,
+    )?;
+
+    if !performance_fee.is_zero() {
+        messages.extend(collect_performance_fee(
+            deps.as_ref(),
+            &config,
+            performance_fee,
+        )?);
+    }
+    logs.push(attr("performance_fee", performance_fee));
+
+    let harvest_bounty = pay_harvest_bounty(
+        deps.branch(),
+        &config,
+        profit_amount.checked_sub(performance_fee)?,
+        &mut messages,
+        &mut logs,
+    )?;
+
+    // Deposited profit is already charged, so it raises the anchor deposit
+    let deposit_amount = profit_amount.checked_sub(performance_fee + harvest_bounty)?;
+    if !deposit_amount.is_zero() {
+        state.anchor_deposited += deposit_amount;
+        deposit_to_anchor(deps.as_ref(), config.clone(), deposit_amount, &mut messages)?;
+        logs.push(attr("deposit_amount", deposit_amount));
     }
+    store_state(deps.storage, &state)?;
+
+    // Harvested profit stays in strategy, only the gain is reported
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.addr_humanize(&config.vault)?.to_string(),
+        msg: to_binary(&VaultExecuteMsg::Report {
+            gain: deposit_amount,
+            loss: Uint128::zero(),
+            debt_payment: Uint128::zero(),
+        })?,
+        funds: vec![],
+    }));
 
     Ok(Response::new().add_messages(messages).add_attributes(logs))
 }
+
+/// Compound pending mirror and anchor rewards and deploy the proceeds,
+/// paying the harvest bounty to the caller out of the harvested profit
+pub fn harvest(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    let mut state = read_state(deps.storage)?;
+    if env.block.time.seconds() < state.last_harvest + config.harvest_interval {
+        return Err(StdError::generic_err("Harvest interval not elapsed"));
+    }
+    state.last_harvest = env.block.time.seconds();
+    store_state(deps.storage, &state)?;
+
+    // Compound hooks pay the bounty out of their proceeds, up to the harvested profit
+    if !config.harvest_bounty.is_zero() {
+        store_harvest_bounty(
+            deps.storage,
+            &HarvestBounty {
+                recipient: deps.api.addr_canonicalize(info.sender.as_str())?,
+                amount: config.harvest_bounty,
+            },
+        )?;
+    }
+
+    // Compound only the rewards pending, claiming nothing would just cost gas
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mirror_reward: Uint128 = query_mirror_staking_rewards(deps.as_ref(), config.clone())?
+        .into_iter()
+        .map(|reward_info| reward_info.pending_reward)
+        .sum();
+    if !mirror_reward.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::CompoundMirror { max_spread: None })?,
+        }));
+    }
+
+    let anchor_reward = query_anchor_pending_rewards(deps.as_ref(), env.clone(), &config)?;
+    if !anchor_reward.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::CompoundAnchor { max_spread: None })?,
+        }));
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::HarvestHook {})?,
+    }));
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::Deploy {})?,
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "harvest"),
+            attr("mirror_reward", mirror_reward),
+            attr("anchor_reward", anchor_reward),
+        ]))
+}
+
+/// Only contract itself can execute
+/// Drop the harvest bounty left unpaid for lack of profit
+pub fn harvest_hook(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    assert_sender_privilege(info.sender.to_string(), env.contract.address.to_string())?;
+
+    remove_harvest_bounty(deps.storage);
+
+    Ok(Response::new())
+}
+
+/// Pay the pending harvest bounty out of `proceeds`, returning the amount paid
+fn pay_harvest_bounty(
+    deps: DepsMut,
+    config: &Config,
+    proceeds: Uint128,
+    messages: &mut Vec<CosmosMsg>,
+    logs: &mut Vec<Attribute>,
+) -> StdResult<Uint128> {
+    let mut harvest_bounty = match read_harvest_bounty(deps.storage)? {
+        Some(harvest_bounty) => harvest_bounty,
+        None => return Ok(Uint128::zero()),
+    };
+
+    let bounty_amount = std::cmp::min(harvest_bounty.amount, proceeds);
+    if bounty_amount.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    messages.push(
+        Asset {
+            info: config.get_underlying(),
+            amount: bounty_amount,
+        }
+        .into_msg(
+            &deps.querier,
+            deps.api.addr_humanize(&harvest_bounty.recipient)?,
+        )?,
+    );
+    logs.push(attr("harvest_bounty", bounty_amount));
+
+    harvest_bounty.amount = harvest_bounty.amount.checked_sub(bounty_amount)?;
+    store_harvest_bounty(deps.storage, &harvest_bounty)?;
+
+    Ok(bounty_amount)
+}
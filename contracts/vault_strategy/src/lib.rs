@@ -1,5 +1,6 @@
 pub mod anchor_helper;
 pub mod contract;
+pub mod fee;
 pub mod invest;
 pub mod manage;
 pub mod mirror_helper;
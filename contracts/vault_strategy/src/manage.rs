@@ -1,17 +1,71 @@
 use cosmwasm_std::{
-    attr, Decimal, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    attr, to_binary, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Uint128, WasmMsg,
 };
 
-use crate::state::{read_config, store_config};
+use crate::mirror_helper::query_mirror_positions;
+use crate::querier::{get_total_balance, query_vault_debt};
+use crate::state::{
+    read_config, read_short_position, read_state, remove_short_position as remove_position,
+    store_config, store_state,
+};
 use athena::access_control::{assert_access_privilege, assert_governance_privilege};
 use athena::asset::Asset;
+use athena::vault::ExecuteMsg as VaultExecuteMsg;
+
+/// Unregister a short position which mirror mint no longer holds,
+/// mirror lock releases its short sale proceeds once the debt is burned
+pub fn remove_short_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_idx: Uint128,
+) -> StdResult<Response> {
+    let config = read_config(deps.storage)?;
+
+    assert_governance_privilege(
+        &deps.querier,
+        deps.api.addr_humanize(&config.controller)?,
+        &info.sender.to_string(),
+    )?;
+
+    if read_short_position(deps.storage, position_idx)?.is_none() {
+        return Err(StdError::generic_err("Short position not found"));
+    }
+
+    if query_mirror_positions(deps.as_ref(), env, config.clone())?
+        .iter()
+        .any(|position| position.idx == position_idx)
+    {
+        return Err(StdError::generic_err("Short position is still open"));
+    }
+
+    remove_position(deps.storage, position_idx);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_short_position"),
+        attr("position_idx", position_idx),
+    ]))
+}
 
 /// Update strategy configuration
+#[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
     controller: Option<String>,
     performance_fee: Option<Decimal>,
+    insurance_fee: Option<Decimal>,
+    anchor_allocation: Option<Decimal>,
+    rebalance_band: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    slippage_tolerance: Option<Decimal>,
+    oracle_price_band: Option<Decimal>,
+    short_ratio_low: Option<Decimal>,
+    short_ratio_high: Option<Decimal>,
+    keeper_bounty: Option<Uint128>,
+    harvest_bounty: Option<Uint128>,
+    harvest_interval: Option<u64>,
 ) -> StdResult<Response> {
     let mut config = read_config(deps.storage)?;
 
@@ -35,6 +89,90 @@ pub fn update_config(
         config.performance_fee = performance_fee;
     }
 
+    if let Some(insurance_fee) = insurance_fee {
+        if insurance_fee > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Insurance fee percentage must be less than 100%",
+            ));
+        }
+
+        config.insurance_fee = insurance_fee;
+    }
+
+    if let Some(anchor_allocation) = anchor_allocation {
+        if anchor_allocation > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Anchor allocation must be less than 100%",
+            ));
+        }
+
+        config.anchor_allocation = anchor_allocation;
+    }
+
+    if let Some(rebalance_band) = rebalance_band {
+        if rebalance_band > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Rebalance band must be less than 100%",
+            ));
+        }
+
+        config.rebalance_band = rebalance_band;
+    }
+
+    if let Some(max_spread) = max_spread {
+        if max_spread > Decimal::one() {
+            return Err(StdError::generic_err("Max spread must be less than 100%"));
+        }
+
+        config.max_spread = max_spread;
+    }
+
+    if let Some(slippage_tolerance) = slippage_tolerance {
+        if slippage_tolerance > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Slippage tolerance must be less than 100%",
+            ));
+        }
+
+        config.slippage_tolerance = slippage_tolerance;
+    }
+
+    if let Some(oracle_price_band) = oracle_price_band {
+        if oracle_price_band > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Oracle price band must be less than 100%",
+            ));
+        }
+
+        config.oracle_price_band = oracle_price_band;
+    }
+
+    if let Some(short_ratio_low) = short_ratio_low {
+        config.short_ratio_low = short_ratio_low;
+    }
+
+    if let Some(short_ratio_high) = short_ratio_high {
+        config.short_ratio_high = short_ratio_high;
+    }
+
+    if config.short_ratio_low >= config.short_ratio_high {
+        return Err(StdError::generic_err(
+            "Short ratio low must be less than short ratio high",
+        ));
+    }
+
+    if let Some(keeper_bounty) = keeper_bounty {
+        config.keeper_bounty = keeper_bounty;
+    }
+
+    if let Some(harvest_bounty) = harvest_bounty {
+        config.harvest_bounty = harvest_bounty;
+    }
+
+    if let Some(harvest_interval) = harvest_interval {
+        config.harvest_interval = harvest_interval;
+    }
+
     store_config(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -42,6 +180,8 @@ pub fn update_config(
 
 /// Withdraw underlying asset to vault
 /// If amount is None, then withdraw all underlying assets
+/// Withdrawal is reported to vault with the debt above the realizable balance as loss,
+/// or sent directly if the vault no longer lists the strategy
 pub fn withdraw_to_vault(
     deps: DepsMut,
     env: Env,
@@ -50,7 +190,8 @@ pub fn withdraw_to_vault(
 ) -> StdResult<Response> {
     let config = read_config(deps.storage)?;
 
-    if info.sender != deps.api.addr_humanize(&config.vault)? {
+    if info.sender != env.contract.address && info.sender != deps.api.addr_humanize(&config.vault)?
+    {
         assert_access_privilege(
             &deps.querier,
             deps.api.addr_humanize(&config.controller)?,
@@ -61,7 +202,7 @@ pub fn withdraw_to_vault(
     let withdraw_amount = if amount.is_none() {
         config
             .get_underlying()
-            .query_balance(&deps.querier, deps.api, env.contract.address)?
+            .query_balance(&deps.querier, deps.api, env.contract.address.clone())?
     } else {
         amount.unwrap()
     };
@@ -74,12 +215,46 @@ pub fn withdraw_to_vault(
         info: config.get_underlying(),
     };
 
+    let vault = deps.api.addr_humanize(&config.vault)?;
+    let mut state = read_state(deps.storage)?;
+
+    // Returned underlying is reported to vault as debt payment
+    let debt_payment = withdraw_asset.deduct_tax(&deps.querier)?;
+
+    // Retired strategy has no debt left, its payment is recorded as returned funds
+    let total_debt = query_vault_debt(deps.as_ref(), env.clone(), config.clone())?;
+
+    // Debt not covered by the balance left after tax is recognized as loss
+    let loss = if total_debt.unwrap_or_default().is_zero() {
+        Uint128::zero()
+    } else {
+        let realizable_balance = get_total_balance(deps.as_ref(), env, config.clone())?
+            .saturating_sub(withdraw_amount.checked_sub(debt_payment.amount)?);
+        total_debt.unwrap_or_default().saturating_sub(realizable_balance)
+    };
+
+    // Net gain is measured against the written down debt after a loss
+    if !loss.is_zero() {
+        state.high_water_mark = Uint128::zero();
+    }
+    state.vault_debt = total_debt
+        .unwrap_or(state.vault_debt)
+        .saturating_sub(debt_payment.amount + loss);
+    store_state(deps.storage, &state)?;
+
     Ok(Response::new()
-        .add_message(
-            withdraw_asset.into_msg(&deps.querier, deps.api.addr_humanize(&config.vault)?)?,
-        )
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: vault.to_string(),
+            msg: to_binary(&VaultExecuteMsg::Report {
+                gain: Uint128::zero(),
+                loss,
+                debt_payment: debt_payment.amount,
+            })?,
+            funds: vec![debt_payment],
+        }))
         .add_attributes(vec![
             attr("action", "withdraw_to_vault"),
             attr("amount", withdraw_amount),
+            attr("loss", loss),
         ]))
 }
@@ -1,11 +1,20 @@
 use cosmwasm_std::{
-    to_binary, Addr, Decimal, Deps, Env, QueryRequest, StdResult, Uint128, WasmQuery,
+    to_binary, Addr, CosmosMsg, Decimal, Deps, Env, Fraction, QueryRequest, StdError, StdResult,
+    Uint128, WasmQuery,
 };
+use std::collections::BTreeMap;
 
-use crate::state::Config;
+use crate::state::{read_short_positions, Config, ShortPosition};
 use athena::asset::{Asset, AssetInfo};
-use athena::querier::query_supply;
+use athena::querier::{query_supply, query_tax_cap, query_tax_rate};
+use mirror_protocol::common::OrderBy;
+use mirror_protocol::lock::{
+    PositionLockInfoResponse as MirrorPositionLockInfoResponse, QueryMsg as MirrorLockQueryMsg,
+};
 use mirror_protocol::mint::{
+    AssetConfigResponse as MirrorAssetConfigResponse, ConfigResponse as MirrorMintConfigResponse,
+    Cw20HookMsg as MirrorMintCw20HookMsg,
+    NextPositionIdxResponse as MirrorNextPositionIdxResponse,
     PositionResponse as MirrorPositionResponse, PositionsResponse as MirrorPositionsResponse,
     QueryMsg as MirrorMintQueryMsg,
 };
@@ -19,10 +28,14 @@ use mirror_protocol::staking::{
 use terraswap::asset::PairInfo;
 use terraswap::factory::QueryMsg as TerraSwapFactoryQueryMsg;
 use terraswap::pair::{
-    QueryMsg as TerraSwapQueryMsg, ReverseSimulationResponse, SimulationResponse,
+    ExecuteMsg as TerraPairExecuteMsg, QueryMsg as TerraSwapQueryMsg, ReverseSimulationResponse,
+    SimulationResponse,
 };
 
-fn query_mirror_asset_price(
+/// Maximum page size of mirror mint positions query
+const MIRROR_POSITIONS_LIMIT: u32 = 30;
+
+pub fn query_mirror_asset_price(
     deps: Deps,
     mirror_oracle: String,
     mirror_asset: String,
@@ -40,6 +53,67 @@ fn query_mirror_asset_price(
     Ok(mirror_oracle_price_response.rate)
 }
 
+/// Return belief price of the swap between underlying and mirror asset
+/// from the mirror oracle, in offer asset per ask asset
+pub fn query_belief_price(
+    deps: Deps,
+    config: &Config,
+    mirror_asset: String,
+    offer_mirror_asset: bool,
+) -> StdResult<Decimal> {
+    let price = query_mirror_asset_price(
+        deps,
+        deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+        mirror_asset,
+        config.stable_denom.clone(),
+    )?;
+
+    if price.is_zero() {
+        return Err(StdError::generic_err("Invalid oracle price"));
+    }
+
+    if offer_mirror_asset {
+        Ok(Decimal::from_ratio(price.denominator(), price.numerator()))
+    } else {
+        Ok(price)
+    }
+}
+
+/// Fail if the pool price of the mirror asset is out of the band around the oracle price
+pub fn assert_pool_price(
+    deps: Deps,
+    config: &Config,
+    mirror_asset: String,
+    pool_stable_balance: Uint128,
+    pool_mirror_balance: Uint128,
+) -> StdResult<()> {
+    if pool_mirror_balance.is_zero() {
+        return Err(StdError::generic_err("Pool is empty"));
+    }
+
+    let oracle_price = query_mirror_asset_price(
+        deps,
+        deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+        mirror_asset,
+        config.stable_denom.clone(),
+    )?;
+    // Compare the stable side of the pool with its value at the oracle price
+    let oracle_stable_balance = pool_mirror_balance * oracle_price;
+
+    let deviation = if pool_stable_balance > oracle_stable_balance {
+        pool_stable_balance.checked_sub(oracle_stable_balance)?
+    } else {
+        oracle_stable_balance.checked_sub(pool_stable_balance)?
+    };
+    if deviation > oracle_stable_balance * config.oracle_price_band {
+        return Err(StdError::generic_err(
+            "Pool price deviates from oracle price",
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn query_mirror_staking_rewards(
     deps: Deps,
     config: Config,
@@ -55,36 +129,102 @@ pub fn query_mirror_staking_rewards(
     Ok(mirror_reward_response.reward_infos)
 }
 
+/// Return the LP of the mirror asset bonded to mirror staking
+pub fn query_mirror_bond_amount(
+    deps: Deps,
+    config: Config,
+    mirror_asset_addr: &str,
+) -> StdResult<Uint128> {
+    Ok(query_mirror_staking_rewards(deps, config)?
+        .into_iter()
+        .find(|reward_info| !reward_info.is_short && reward_info.asset_token == mirror_asset_addr)
+        .map(|reward_info| reward_info.bond_amount)
+        .unwrap_or_else(Uint128::zero))
+}
+
+/// Return all positions mirror mint holds for this strategy
 pub fn query_mirror_positions(
     deps: Deps,
     env: Env,
     config: Config,
 ) -> StdResult<Vec<MirrorPositionResponse>> {
-    let mirror_position_res: MirrorPositionsResponse =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
-            msg: to_binary(&MirrorMintQueryMsg::Positions {
-                owner_addr: Some(env.contract.address.to_string()),
-                asset_token: None,
-                start_after: None,
-                limit: None,
-                order_by: None,
-            })?,
-        }))?;
-    Ok(mirror_position_res.positions)
+    let mut positions: Vec<MirrorPositionResponse> = vec![];
+    loop {
+        let mirror_position_res: MirrorPositionsResponse =
+            deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+                msg: to_binary(&MirrorMintQueryMsg::Positions {
+                    owner_addr: Some(env.contract.address.to_string()),
+                    asset_token: None,
+                    start_after: positions.last().map(|position| position.idx),
+                    limit: Some(MIRROR_POSITIONS_LIMIT),
+                    order_by: Some(OrderBy::Asc),
+                })?,
+            }))?;
+
+        let page_size = mirror_position_res.positions.len();
+        positions.extend(mirror_position_res.positions);
+        if page_size < MIRROR_POSITIONS_LIMIT as usize {
+            return Ok(positions);
+        }
+    }
+}
+
+/// Return the registered short positions with their mirror position,
+/// skipping the positions mirror mint no longer holds after close or liquidation
+pub fn query_open_short_positions(
+    deps: Deps,
+    env: Env,
+    config: Config,
+) -> StdResult<Vec<(Uint128, ShortPosition, MirrorPositionResponse)>> {
+    let short_positions = read_short_positions(deps.storage)?;
+    if short_positions.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut mirror_positions: BTreeMap<Uint128, MirrorPositionResponse> =
+        query_mirror_positions(deps, env, config)?
+            .into_iter()
+            .map(|position| (position.idx, position))
+            .collect();
+
+    Ok(short_positions
+        .into_iter()
+        .filter_map(|(position_idx, short_position)| {
+            mirror_positions
+                .remove(&position_idx)
+                .map(|position| (position_idx, short_position, position))
+        })
+        .collect())
 }
 
 pub fn get_mirror_balance(deps: Deps, config: Config) -> StdResult<Uint128> {
     let reward_infos = query_mirror_staking_rewards(deps, config.clone())?;
     let mut total_staked_in_denom = Uint128::zero();
+    let mut short_reward = Uint128::zero();
 
     for reward_info in reward_infos {
-        if reward_info.bond_amount.is_zero() || reward_info.is_short {
+        if reward_info.is_short {
+            short_reward += reward_info.pending_reward;
+            continue;
+        }
+        if reward_info.bond_amount.is_zero() {
             continue;
         }
 
-        let mirror_asset = AssetInfo::Token {
-            contract_addr: reward_info.asset_token.clone(),
+        let staked_amount_in_denom = get_lp_oracle_value(
+            deps,
+            &config,
+            reward_info.asset_token,
+            reward_info.bond_amount,
+        )?;
+        total_staked_in_denom = total_staked_in_denom + staked_amount_in_denom;
+    }
+
+    // Pending short rewards are only worth their MIR
+    if !short_reward.is_zero() {
+        let mirror_token = AssetInfo::Token {
+            contract_addr: deps.api.addr_humanize(&config.mirror_token)?.to_string(),
         };
         let pair_info = query_pair_info(
             deps,
@@ -92,39 +232,68 @@ pub fn get_mirror_balance(deps: Deps, config: Config) -> StdResult<Uint128> {
                 .addr_humanize(&config.terraswap_factory)?
                 .to_string(),
             config.get_underlying(),
-            mirror_asset.clone(),
-        )?;
-
-        let denom_balance = config.get_underlying().query_balance(
-            &deps.querier,
-            deps.api,
-            Addr::unchecked(pair_info.contract_addr.to_string()),
+            mirror_token.clone(),
         )?;
-
-        let m_asset_balance = mirror_asset.query_balance(
-            &deps.querier,
-            deps.api,
-            Addr::unchecked(pair_info.contract_addr.to_string()),
-        )?;
-        let total_denom_balance = m_asset_balance
-            * query_mirror_asset_price(
-                deps,
-                deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
-                reward_info.asset_token,
-                config.stable_denom.clone(),
-            )?
-            + denom_balance;
-        let lp_total_supply = query_supply(
-            &deps.querier,
-            Addr::unchecked(pair_info.liquidity_token.to_string()),
+        total_staked_in_denom += simulate_swap(
+            deps,
+            Asset {
+                info: mirror_token,
+                amount: short_reward,
+            },
+            pair_info.contract_addr,
         )?;
-        let staked_amount_in_denom =
-            total_denom_balance * Decimal::from_ratio(reward_info.bond_amount, lp_total_supply);
-        total_staked_in_denom = total_staked_in_denom + staked_amount_in_denom;
     }
+
     Ok(total_staked_in_denom)
 }
 
+/// Value `lp_amount` of the mirror asset liquidity at the pool reserves,
+/// pricing the mirror asset reserve at the mirror oracle price
+pub fn get_lp_oracle_value(
+    deps: Deps,
+    config: &Config,
+    mirror_asset_addr: String,
+    lp_amount: Uint128,
+) -> StdResult<Uint128> {
+    let mirror_asset = AssetInfo::Token {
+        contract_addr: mirror_asset_addr.clone(),
+    };
+    let pair_info = query_pair_info(
+        deps,
+        deps.api
+            .addr_humanize(&config.terraswap_factory)?
+            .to_string(),
+        config.get_underlying(),
+        mirror_asset.clone(),
+    )?;
+
+    let denom_balance = config.get_underlying().query_balance(
+        &deps.querier,
+        deps.api,
+        Addr::unchecked(pair_info.contract_addr.to_string()),
+    )?;
+
+    let m_asset_balance = mirror_asset.query_balance(
+        &deps.querier,
+        deps.api,
+        Addr::unchecked(pair_info.contract_addr.to_string()),
+    )?;
+    let total_denom_balance = m_asset_balance
+        * query_mirror_asset_price(
+            deps,
+            deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+            mirror_asset_addr,
+            config.stable_denom.clone(),
+        )?
+        + denom_balance;
+    let lp_total_supply = query_supply(
+        &deps.querier,
+        Addr::unchecked(pair_info.liquidity_token.to_string()),
+    )?;
+
+    Ok(total_denom_balance * Decimal::from_ratio(lp_amount, lp_total_supply))
+}
+
 pub fn query_pair_info(
     deps: Deps,
     terraswap_factory: String,
@@ -153,6 +322,22 @@ pub fn simulate_swap(deps: Deps, offer_asset: Asset, swap_pair: String) -> StdRe
     Ok(simulate_response.return_amount)
 }
 
+/// Return the underlying received for swapping `offer_asset`,
+/// net of the tax the pair deducts when sending it
+pub fn simulate_swap_to_underlying(
+    deps: Deps,
+    config: &Config,
+    offer_asset: Asset,
+    swap_pair: String,
+) -> StdResult<Uint128> {
+    Ok(Asset {
+        info: config.get_underlying(),
+        amount: simulate_swap(deps, offer_asset, swap_pair)?,
+    }
+    .deduct_tax(&deps.querier)?
+    .amount)
+}
+
 pub fn reserve_simulate_swap(
     deps: Deps,
     ask_asset: Asset,
@@ -232,3 +417,144 @@ pub fn query_mirror_position(
         }))?;
     Ok(mirror_position_response)
 }
+
+/// Return the index mirror mint assigns to the next opened position
+pub fn query_next_position_idx(deps: Deps, config: &Config) -> StdResult<Uint128> {
+    let next_position_idx_response: MirrorNextPositionIdxResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.into(),
+            msg: to_binary(&MirrorMintQueryMsg::NextPositionIdx {})?,
+        }))?;
+    Ok(next_position_idx_response.next_position_idx)
+}
+
+pub fn query_mirror_asset_config(
+    deps: Deps,
+    config: &Config,
+    asset_token: String,
+) -> StdResult<MirrorAssetConfigResponse> {
+    let asset_config_response: MirrorAssetConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_humanize(&config.mirror_mint)?.into(),
+            msg: to_binary(&MirrorMintQueryMsg::AssetConfig { asset_token })?,
+        }))?;
+    Ok(asset_config_response)
+}
+
+pub fn query_mirror_mint_config(
+    deps: Deps,
+    mirror_mint: &String,
+) -> StdResult<MirrorMintConfigResponse> {
+    let mint_config_response: MirrorMintConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: String::from(mirror_mint),
+            msg: to_binary(&MirrorMintQueryMsg::Config {})?,
+        }))?;
+    Ok(mint_config_response)
+}
+
+pub fn query_position_lock_info(
+    deps: Deps,
+    config: &Config,
+    position_idx: Uint128,
+) -> StdResult<MirrorPositionLockInfoResponse> {
+    let lock_info_response: MirrorPositionLockInfoResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_humanize(&config.mirror_lock)?.into(),
+            msg: to_binary(&MirrorLockQueryMsg::PositionLockInfo { position_idx })?,
+        }))?;
+    Ok(lock_info_response)
+}
+
+/// Return the lock info of the registered positions whose sale proceeds are held by the mirror lock.
+/// Mirror lock holds the proceeds until they are unlocked, or releases them
+/// once mirror mint burns the whole debt of the position
+pub fn query_locked_short_positions(
+    deps: Deps,
+    env: Env,
+    config: Config,
+) -> StdResult<Vec<(Uint128, MirrorPositionLockInfoResponse)>> {
+    let mut locked_positions = vec![];
+    for (position_idx, short_position, position) in
+        query_open_short_positions(deps, env, config.clone())?
+    {
+        if !short_position.proceeds_locked || position.asset.amount.is_zero() {
+            continue;
+        }
+
+        let lock_info = query_position_lock_info(deps, &config, position_idx)?;
+        if !lock_info.locked_amount.is_zero() {
+            locked_positions.push((position_idx, lock_info));
+        }
+    }
+
+    Ok(locked_positions)
+}
+
+/// Return the short sale proceeds of the registered positions held by the mirror lock
+pub fn get_locked_proceeds(deps: Deps, env: Env, config: Config) -> StdResult<Uint128> {
+    let mut locked_proceeds = Uint128::zero();
+    for (_, lock_info) in query_locked_short_positions(deps, env, config)? {
+        locked_proceeds += lock_info.locked_amount;
+    }
+
+    Ok(locked_proceeds)
+}
+
+/// Buy the mirror asset with underlying and burn it against a short position
+pub fn burn_mirror_asset(
+    deps: Deps,
+    config: &Config,
+    burn_asset: Asset,
+    position_idx: Uint128,
+    max_spread: Option<Decimal>,
+    messages: &mut Vec<CosmosMsg>,
+) -> StdResult<()> {
+    let pair_info = query_pair_info(
+        deps,
+        deps.api
+            .addr_humanize(&config.terraswap_factory)?
+            .to_string(),
+        config.get_underlying(),
+        burn_asset.info.clone(),
+    )?;
+
+    let reserve_simulation =
+        reserve_simulate_swap(deps, burn_asset.clone(), pair_info.contract_addr.clone())?;
+
+    // Add the tax on top of the simulated offer, rounding up,
+    // so that the pair receives enough UST to return the burn amount
+    let offer_amount = reserve_simulation.offer_amount;
+    let tax = std::cmp::min(
+        offer_amount * query_tax_rate(&deps.querier)? + Uint128::from(1u128),
+        query_tax_cap(&deps.querier, config.stable_denom.clone())?,
+    );
+    let offer_asset = Asset {
+        info: config.get_underlying(),
+        amount: offer_amount + tax,
+    };
+    let offer_asset_tax_deducted = Asset {
+        info: config.get_underlying(),
+        amount: offer_asset.deduct_tax(&deps.querier)?.amount,
+    };
+
+    let belief_price = query_belief_price(deps, config, burn_asset.info.to_string(), false)?;
+
+    messages.push(offer_asset.into_msg_with_data(
+        &deps.querier,
+        pair_info.contract_addr,
+        to_binary(&TerraPairExecuteMsg::Swap {
+            offer_asset: offer_asset_tax_deducted.into(),
+            belief_price: Some(belief_price),
+            max_spread: Some(config.get_max_spread(max_spread)),
+            to: None,
+        })?,
+    )?);
+    messages.push(burn_asset.into_msg_with_data(
+        &deps.querier,
+        deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+        to_binary(&MirrorMintCw20HookMsg::Burn { position_idx })?,
+    )?);
+
+    Ok(())
+}
@@ -1,9 +1,26 @@
-use cosmwasm_std::{Deps, Env, StdResult, Uint128};
+use cosmwasm_std::{
+    to_binary, Addr, Decimal, Deps, Env, QueryRequest, StdResult, Uint128, WasmQuery,
+};
+use std::collections::BTreeMap;
 
-use crate::anchor_helper::get_anchor_balance;
-use crate::mirror_helper::get_mirror_balance;
-use crate::state::{read_config, read_state, Config};
-use athena::vault_strategy::{ConfigResponse, State};
+use crate::anchor_helper::{
+    get_anchor_balance, get_anchor_balance_without_fee, query_anchor_exchange_rate,
+};
+use crate::fee::get_accrued_performance_fee;
+use crate::mirror_helper::{
+    get_locked_proceeds, get_lp_oracle_value, get_lp_value, get_mirror_balance,
+    query_mirror_asset_config, query_mirror_asset_price, query_mirror_staking_rewards,
+    query_open_short_positions, query_pair_info,
+};
+use crate::state::{read_config, read_mirror_positions, read_state, Config};
+use athena::asset::AssetInfo;
+use athena::querier::{query_supply, query_token_balance};
+use athena::vault::{QueryMsg as VaultQueryMsg, StrategiesResponse, StrategyDebtResponse};
+use athena::vault_strategy::{
+    BalancesResponse, ConfigResponse, MirrorBalanceInfo, MirrorPositionInfo,
+    MirrorPositionsResponse, NetDeltaInfo, NetDeltaResponse, ShortPositionInfo,
+    ShortPositionsResponse, State,
+};
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = read_config(deps.storage)?;
@@ -13,12 +30,25 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         controller: deps.api.addr_humanize(&config.controller)?.to_string(),
         vault: deps.api.addr_humanize(&config.vault)?.to_string(),
         performance_fee: config.performance_fee,
+        insurance_fee: config.insurance_fee,
+        anchor_allocation: config.anchor_allocation,
+        rebalance_band: config.rebalance_band,
+        max_spread: config.max_spread,
+        slippage_tolerance: config.slippage_tolerance,
+        oracle_price_band: config.oracle_price_band,
+        short_ratio_low: config.short_ratio_low,
+        short_ratio_high: config.short_ratio_high,
+        keeper_bounty: config.keeper_bounty,
+        harvest_bounty: config.harvest_bounty,
+        harvest_interval: config.harvest_interval,
         stable_denom: config.stable_denom,
         anchor_market: deps.api.addr_humanize(&config.anchor_market)?.to_string(),
         aterra_contract: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+        anchor_token: deps.api.addr_humanize(&config.anchor_token)?.to_string(),
         mirror_token: deps.api.addr_humanize(&config.mirror_token)?.to_string(),
         mirror_staking: deps.api.addr_humanize(&config.mirror_staking)?.to_string(),
         mirror_mint: deps.api.addr_humanize(&config.mirror_mint)?.to_string(),
+        mirror_lock: deps.api.addr_humanize(&config.mirror_lock)?.to_string(),
         mirror_oracle: deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
         terraswap_factory: deps
             .api
@@ -35,11 +65,244 @@ pub fn query_state(deps: Deps) -> StdResult<State> {
     Ok(state)
 }
 
+pub fn query_mirror_lp_positions(deps: Deps) -> StdResult<MirrorPositionsResponse> {
+    let config = read_config(deps.storage)?;
+
+    let positions = read_mirror_positions(deps.storage)?
+        .into_iter()
+        .map(|(mirror_asset, position)| {
+            let mirror_asset = deps.api.addr_humanize(&mirror_asset)?.to_string();
+            let value = if position.lp_amount.is_zero() {
+                Uint128::zero()
+            } else {
+                get_lp_value(
+                    deps,
+                    deps.api
+                        .addr_humanize(&config.terraswap_factory)?
+                        .to_string(),
+                    config.get_underlying(),
+                    AssetInfo::Token {
+                        contract_addr: mirror_asset.clone(),
+                    },
+                    position.lp_amount,
+                )?
+            };
+
+            let total_value = value + position.realized_proceeds;
+            let total_cost = position.cost + position.realized_cost;
+
+            Ok(MirrorPositionInfo {
+                mirror_asset,
+                cost: position.cost,
+                lp_amount: position.lp_amount,
+                value,
+                realized_proceeds: position.realized_proceeds,
+                realized_cost: position.realized_cost,
+                profit: total_value.saturating_sub(total_cost),
+                loss: total_cost.saturating_sub(total_value),
+            })
+        })
+        .collect::<StdResult<Vec<MirrorPositionInfo>>>()?;
+
+    Ok(MirrorPositionsResponse { positions })
+}
+
+pub fn query_short_positions(deps: Deps, env: Env) -> StdResult<ShortPositionsResponse> {
+    let config = read_config(deps.storage)?;
+
+    let short_positions = query_open_short_positions(deps, env.clone(), config.clone())?;
+    let exchange_rate = if short_positions.is_empty() {
+        Decimal::zero()
+    } else {
+        query_anchor_exchange_rate(
+            deps,
+            Some(env.block.height),
+            &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+        )?
+    };
+
+    let positions = short_positions
+        .into_iter()
+        .map(|(position_idx, short_position, position)| {
+            let mirror_asset = deps
+                .api
+                .addr_humanize(&short_position.mirror_asset)?
+                .to_string();
+            let asset_config = query_mirror_asset_config(deps, &config, mirror_asset.clone())?;
+
+            let asset_value = position.asset.amount
+                * query_mirror_asset_price(
+                    deps,
+                    deps.api.addr_humanize(&config.mirror_oracle)?.to_string(),
+                    mirror_asset.clone(),
+                    config.stable_denom.clone(),
+                )?;
+            let collateral_value = position.collateral.amount * exchange_rate;
+            let collateral_ratio = if asset_value.is_zero() {
+                Decimal::zero()
+            } else {
+                Decimal::from_ratio(collateral_value, asset_value)
+            };
+            let liquidation_distance = if collateral_ratio > asset_config.min_collateral_ratio {
+                collateral_ratio - asset_config.min_collateral_ratio
+            } else {
+                Decimal::zero()
+            };
+
+            Ok(ShortPositionInfo {
+                position_idx,
+                mirror_asset,
+                opening_collateral: short_position.collateral,
+                opening_collateral_ratio: short_position.collateral_ratio,
+                opened_at: short_position.opened_at,
+                collateral: position.collateral.amount,
+                asset_amount: position.asset.amount,
+                collateral_ratio,
+                min_collateral_ratio: asset_config.min_collateral_ratio,
+                liquidation_distance,
+            })
+        })
+        .collect::<StdResult<Vec<ShortPositionInfo>>>()?;
+
+    Ok(ShortPositionsResponse { positions })
+}
+
+pub fn query_net_delta(deps: Deps, env: Env) -> StdResult<NetDeltaResponse> {
+    let config = read_config(deps.storage)?;
+
+    // (long amount, short amount) by mirror asset
+    let mut deltas: BTreeMap<String, (Uint128, Uint128)> = BTreeMap::new();
+
+    for reward_info in query_mirror_staking_rewards(deps, config.clone())? {
+        if reward_info.bond_amount.is_zero() || reward_info.is_short {
+            continue;
+        }
+
+        let mirror_asset = AssetInfo::Token {
+            contract_addr: reward_info.asset_token.clone(),
+        };
+        let pair_info = query_pair_info(
+            deps,
+            deps.api
+                .addr_humanize(&config.terraswap_factory)?
+                .to_string(),
+            config.get_underlying(),
+            mirror_asset.clone(),
+        )?;
+        let pool_mirror_balance = mirror_asset.query_balance(
+            &deps.querier,
+            deps.api,
+            Addr::unchecked(pair_info.contract_addr),
+        )?;
+        let lp_supply = query_supply(&deps.querier, Addr::unchecked(pair_info.liquidity_token))?;
+
+        deltas.entry(reward_info.asset_token).or_default().0 +=
+            pool_mirror_balance.multiply_ratio(reward_info.bond_amount, lp_supply);
+    }
+
+    for (_, _, position) in query_open_short_positions(deps, env.clone(), config.clone())? {
+        deltas.entry(position.asset.info.to_string()).or_default().1 += position.asset.amount;
+    }
+
+    let assets = deltas
+        .into_iter()
+        .map(|(mirror_asset, (staked_amount, short_amount))| {
+            let idle_amount = query_token_balance(
+                &deps.querier,
+                Addr::unchecked(mirror_asset.clone()),
+                env.contract.address.clone(),
+            )?;
+            let long_amount = staked_amount + idle_amount;
+
+            Ok(NetDeltaInfo {
+                mirror_asset,
+                long_amount,
+                short_amount,
+                net_long: long_amount.saturating_sub(short_amount),
+                net_short: short_amount.saturating_sub(long_amount),
+            })
+        })
+        .collect::<StdResult<Vec<NetDeltaInfo>>>()?;
+
+    Ok(NetDeltaResponse { assets })
+}
+
+pub fn query_balances(deps: Deps, env: Env) -> StdResult<BalancesResponse> {
+    let config = read_config(deps.storage)?;
+
+    let mirror_balances = query_mirror_staking_rewards(deps, config.clone())?
+        .into_iter()
+        .filter(|reward_info| !reward_info.is_short && !reward_info.bond_amount.is_zero())
+        .map(|reward_info| {
+            Ok(MirrorBalanceInfo {
+                value: get_lp_oracle_value(
+                    deps,
+                    &config,
+                    reward_info.asset_token.clone(),
+                    reward_info.bond_amount,
+                )?,
+                mirror_asset: reward_info.asset_token,
+                lp_amount: reward_info.bond_amount,
+            })
+        })
+        .collect::<StdResult<Vec<MirrorBalanceInfo>>>()?;
+
+    let short_positions = query_open_short_positions(deps, env.clone(), config.clone())?;
+    let short_collateral = if short_positions.is_empty() {
+        Uint128::zero()
+    } else {
+        let exchange_rate = query_anchor_exchange_rate(
+            deps,
+            Some(env.block.height),
+            &deps.api.addr_humanize(&config.anchor_market)?.to_string(),
+        )?;
+        let mut aterra_collateral = Uint128::zero();
+        for (_, _, position) in short_positions {
+            aterra_collateral += position.collateral.amount;
+        }
+        aterra_collateral * exchange_rate
+    };
+
+    Ok(BalancesResponse {
+        idle_balance: config.get_underlying().query_balance(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?,
+        anchor_balance: get_anchor_balance(
+            deps,
+            config.clone(),
+            env.contract.address.clone(),
+            env.block.height,
+        )?,
+        anchor_balance_without_fee: get_anchor_balance_without_fee(
+            deps,
+            env.clone(),
+            config.clone(),
+        )?,
+        mirror_balances,
+        short_collateral,
+        locked_proceeds: get_locked_proceeds(deps, env, config)?,
+    })
+}
+
 pub fn query_total_balance(deps: Deps, env: Env) -> StdResult<Uint128> {
     let config = read_config(deps.storage)?;
     Ok(get_total_balance(deps, env, config)?)
 }
 
+pub fn query_net_total_balance(deps: Deps, env: Env) -> StdResult<Uint128> {
+    let config = read_config(deps.storage)?;
+    let total_balance = get_total_balance(deps, env.clone(), config.clone())?;
+
+    Ok(total_balance.checked_sub(get_accrued_performance_fee(
+        deps,
+        env,
+        &config,
+        total_balance,
+    )?)?)
+}
+
 pub fn get_total_balance(deps: Deps, env: Env, config: Config) -> StdResult<Uint128> {
     Ok(config.get_underlying().query_balance(
         &deps.querier,
@@ -50,5 +313,34 @@ pub fn get_total_balance(deps: Deps, env: Env, config: Config) -> StdResult<Uint
         config.clone(),
         env.contract.address.clone(),
         env.block.height,
-    )? + get_mirror_balance(deps, config.clone())?)
+    )? + get_mirror_balance(deps, config.clone())?
+        + get_locked_proceeds(deps, env, config)?)
+}
+
+/// Return the amount lent by the vault to this strategy,
+/// or None if the vault does not list this strategy
+pub fn query_vault_debt(deps: Deps, env: Env, config: Config) -> StdResult<Option<Uint128>> {
+    let vault = deps.api.addr_humanize(&config.vault)?.to_string();
+    let strategies: StrategiesResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: vault.clone(),
+            msg: to_binary(&VaultQueryMsg::Strategies {})?,
+        }))?;
+    if !strategies
+        .strategies
+        .iter()
+        .any(|strategy| strategy.addr == env.contract.address.as_str())
+    {
+        return Ok(None);
+    }
+
+    let strategy_debt: StrategyDebtResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: vault,
+            msg: to_binary(&VaultQueryMsg::StrategyDebt {
+                strategy: env.contract.address.to_string(),
+            })?,
+        }))?;
+
+    Ok(Some(strategy_debt.total_debt))
 }
@@ -1,14 +1,17 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Api, CanonicalAddr, Decimal, StdResult, Storage};
-use cosmwasm_storage::{singleton, singleton_read};
+use cosmwasm_std::{Api, CanonicalAddr, Decimal, Order, StdResult, Storage, Uint128};
+use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
 
 use athena::asset::AssetInfo;
 use athena::vault_strategy::State;
 
 const KEY_CONFIG: &[u8] = b"config";
 const KEY_STRATEGY_STATE: &[u8] = b"state";
+const PREFIX_KEY_MIRROR_POSITION: &[u8] = b"mirror_position";
+const PREFIX_KEY_SHORT_POSITION: &[u8] = b"short_position";
+const KEY_HARVEST_BOUNTY: &[u8] = b"harvest_bounty";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -16,12 +19,33 @@ pub struct Config {
     pub controller: CanonicalAddr,
     pub vault: CanonicalAddr,
     pub performance_fee: Decimal,
+    pub insurance_fee: Decimal,
+    pub anchor_allocation: Decimal,
+    pub rebalance_band: Decimal,
+    /// Default max spread of terraswap swaps
+    pub max_spread: Decimal,
+    /// Slippage tolerance of mirror auto stake
+    pub slippage_tolerance: Decimal,
+    /// Allowed deviation of pool price from oracle price when providing liquidity
+    pub oracle_price_band: Decimal,
+    /// Target collateral ratio band of short positions
+    pub short_ratio_low: Decimal,
+    pub short_ratio_high: Decimal,
+    /// UST paid to the keeper restoring a short position
+    pub keeper_bounty: Uint128,
+    /// UST paid to the caller of Harvest
+    pub harvest_bounty: Uint128,
+    /// Minimum seconds between harvests
+    pub harvest_interval: u64,
     pub stable_denom: String,
     pub anchor_market: CanonicalAddr,
     pub aterra_contract: CanonicalAddr,
+    pub anchor_token: CanonicalAddr,
     pub mirror_token: CanonicalAddr,
     pub mirror_staking: CanonicalAddr,
     pub mirror_mint: CanonicalAddr,
+    /// Mirror lock contract holding short sale proceeds
+    pub mirror_lock: CanonicalAddr,
     pub mirror_oracle: CanonicalAddr,
     pub terraswap_factory: CanonicalAddr,
 }
@@ -33,6 +57,11 @@ impl Config {
         }
     }
 
+    /// Return the max spread override or the default max spread
+    pub fn get_max_spread(&self, max_spread: Option<Decimal>) -> Decimal {
+        max_spread.unwrap_or(self.max_spread)
+    }
+
     pub fn get_aterra_asset_info(&self, api: &dyn Api) -> StdResult<AssetInfo> {
         Ok(AssetInfo::Token {
             contract_addr: api.addr_humanize(&self.aterra_contract)?.to_string(),
@@ -40,12 +69,40 @@ impl Config {
     }
 }
 
+/// Cost basis of mirror liquidity of an asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct MirrorPosition {
+    pub cost: Uint128,
+    pub lp_amount: Uint128,
+    pub realized_proceeds: Uint128,
+    pub realized_cost: Uint128,
+}
+
+/// Short position opened by the strategy in mirror mint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShortPosition {
+    pub mirror_asset: CanonicalAddr,
+    /// aUST collateral deposited when opening and by CheckShortPosition top-ups
+    pub collateral: Uint128,
+    pub collateral_ratio: Decimal,
+    pub opened_at: u64,
+    /// Sale proceeds are held by the mirror lock until UnlockShortProceeds
+    pub proceeds_locked: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MirrorAsset {
     pub swap_pair: CanonicalAddr,
     pub liquidity_token: CanonicalAddr,
 }
 
+/// Bounty owed to the caller of Harvest, paid out of the harvested proceeds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HarvestBounty {
+    pub recipient: CanonicalAddr,
+    pub amount: Uint128,
+}
+
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
     singleton(storage, KEY_CONFIG).save(config)
 }
@@ -61,3 +118,76 @@ pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
 pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     singleton_read(storage, KEY_STRATEGY_STATE).load()
 }
+
+pub fn store_mirror_position(
+    storage: &mut dyn Storage,
+    mirror_asset: &CanonicalAddr,
+    position: &MirrorPosition,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_KEY_MIRROR_POSITION).save(mirror_asset.as_slice(), position)
+}
+
+pub fn read_mirror_position(
+    storage: &dyn Storage,
+    mirror_asset: &CanonicalAddr,
+) -> StdResult<MirrorPosition> {
+    Ok(bucket_read(storage, PREFIX_KEY_MIRROR_POSITION)
+        .may_load(mirror_asset.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn read_mirror_positions(
+    storage: &dyn Storage,
+) -> StdResult<Vec<(CanonicalAddr, MirrorPosition)>> {
+    bucket_read(storage, PREFIX_KEY_MIRROR_POSITION)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (key, position) = item?;
+            Ok((CanonicalAddr::from(key), position))
+        })
+        .collect()
+}
+
+pub fn store_short_position(
+    storage: &mut dyn Storage,
+    position_idx: Uint128,
+    position: &ShortPosition,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_KEY_SHORT_POSITION).save(&position_idx.u128().to_be_bytes(), position)
+}
+
+pub fn read_short_position(
+    storage: &dyn Storage,
+    position_idx: Uint128,
+) -> StdResult<Option<ShortPosition>> {
+    bucket_read(storage, PREFIX_KEY_SHORT_POSITION).may_load(&position_idx.u128().to_be_bytes())
+}
+
+pub fn remove_short_position(storage: &mut dyn Storage, position_idx: Uint128) {
+    bucket::<ShortPosition>(storage, PREFIX_KEY_SHORT_POSITION)
+        .remove(&position_idx.u128().to_be_bytes())
+}
+
+pub fn read_short_positions(storage: &dyn Storage) -> StdResult<Vec<(Uint128, ShortPosition)>> {
+    bucket_read(storage, PREFIX_KEY_SHORT_POSITION)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (key, position) = item?;
+            let mut position_idx = [0u8; 16];
+            position_idx.copy_from_slice(&key);
+            Ok((Uint128::from(u128::from_be_bytes(position_idx)), position))
+        })
+        .collect()
+}
+
+pub fn store_harvest_bounty(storage: &mut dyn Storage, bounty: &HarvestBounty) -> StdResult<()> {
+    singleton(storage, KEY_HARVEST_BOUNTY).save(bounty)
+}
+
+pub fn read_harvest_bounty(storage: &dyn Storage) -> StdResult<Option<HarvestBounty>> {
+    singleton_read(storage, KEY_HARVEST_BOUNTY).may_load()
+}
+
+pub fn remove_harvest_bounty(storage: &mut dyn Storage) {
+    singleton::<HarvestBounty>(storage, KEY_HARVEST_BOUNTY).remove()
+}
@@ -1,12 +1,14 @@
 #[cfg(test)]
 mod tests {
-    use crate::contract::{instantiate, query};
+    use crate::contract::{instantiate, migrate, query};
     use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
 
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{from_binary, Coin, Decimal, OwnedDeps, StdError, Uint128};
-    use athena::vault_strategy::{ConfigResponse, InstantiateMsg, QueryMsg, State};
+    use athena::vault_strategy::{ConfigResponse, InstantiateMsg, MigrateMsg, QueryMsg, State};
+    use cosmwasm_storage::singleton;
     use mirror_protocol::staking::RewardInfoResponseItem as MirrorStakingRewardInfoResponseItem;
+    use serde::{Deserialize, Serialize};
 
     #[test]
     fn instantiate_strategy_fails_if_performace_fee_is_greater_than_100() {
@@ -18,6 +20,7 @@ mod tests {
             performance_fee: Decimal::percent(101u64),
             stable_denom: String::from("uusd"),
             anchor_market: String::from("anchor_market"),
+            anchor_token: String::from("anchor_token"),
             mirror_token: String::from("mirror_token"),
             mirror_staking: String::from("mirror_staking"),
             mirror_mint: String::from("mirror_mint"),
@@ -43,6 +46,7 @@ mod tests {
             performance_fee: Decimal::percent(5u64),
             stable_denom: String::from("uusd"),
             anchor_market: String::from("anchor_market"),
+            anchor_token: String::from("anchor_token"),
             mirror_token: String::from("mirror_token"),
             mirror_staking: String::from("mirror_staking"),
             mirror_mint: String::from("mirror_mint"),
@@ -62,12 +66,25 @@ mod tests {
                 controller: String::from("controller"),
                 vault: String::from("vault"),
                 performance_fee: Decimal::percent(5u64),
+                insurance_fee: Decimal::zero(),
+                anchor_allocation: Decimal::zero(),
+                rebalance_band: Decimal::percent(5u64),
+                max_spread: Decimal::percent(1u64),
+                slippage_tolerance: Decimal::percent(1u64),
+                oracle_price_band: Decimal::percent(5u64),
+                short_ratio_low: Decimal::percent(200u64),
+                short_ratio_high: Decimal::percent(300u64),
+                keeper_bounty: Uint128::zero(),
+                harvest_bounty: Uint128::zero(),
+                harvest_interval: 86400u64,
                 stable_denom: String::from("uusd"),
                 anchor_market: String::from("anchor_market"),
                 aterra_contract: String::from("aterra_contract"),
+                anchor_token: String::from("anchor_token"),
                 mirror_token: String::from("mirror_token"),
                 mirror_staking: String::from("mirror_staking"),
                 mirror_mint: String::from("mirror_mint"),
+                mirror_lock: String::from("mirror_lock"),
                 mirror_oracle: String::from("mirror_oracle"),
                 terraswap_factory: String::from("terraswap_factory"),
             },
@@ -86,6 +103,9 @@ mod tests {
             State {
                 anchor_deposited: Uint128::zero(),
                 aterra_collateral: Uint128::zero(),
+                high_water_mark: Uint128::zero(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero(),
             },
             state
         );
@@ -270,6 +290,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn query_net_total_balance() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u64),
+            }],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
+        deps.querier.with_strategy_debt(Uint128::from(800000u64));
+
+        // 5% performance fee accrues on 200000 of gain above the debt
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::NetTotalBalance {}).unwrap();
+        let net_total_balance: Uint128 = from_binary(&res).unwrap();
+        assert_eq!(net_total_balance, Uint128::from(990000u64));
+
+        // Gain below the debt accrues no fee
+        deps.querier.with_strategy_debt(Uint128::from(1200000u64));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::NetTotalBalance {}).unwrap();
+        let net_total_balance: Uint128 = from_binary(&res).unwrap();
+        assert_eq!(net_total_balance, Uint128::from(1000000u64));
+    }
+
+    #[test]
+    fn migrate_rejects_legacy_storage() {
+        let mut deps = _instantiate_strategy();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        // State of the single strategy vault has no fee or harvest fields
+        #[derive(Serialize, Deserialize)]
+        struct LegacyState {
+            anchor_deposited: Uint128,
+            aterra_collateral: Uint128,
+        }
+        singleton(&mut deps.storage, b"state")
+            .save(&LegacyState {
+                anchor_deposited: Uint128::zero(),
+                aterra_collateral: Uint128::zero(),
+            })
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err(
+                "Legacy strategy storage, deploy a new strategy and use vault MigrateStrategy"
+            )
+        );
+    }
+
     // utils
     fn _instantiate_strategy() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
         let mut deps = mock_dependencies_with_querier(20, &[]);
@@ -280,6 +361,7 @@ mod tests {
             performance_fee: Decimal::percent(5u64),
             stable_denom: String::from("uusd"),
             anchor_market: String::from("anchor_market"),
+            anchor_token: String::from("anchor_token"),
             mirror_token: String::from("mirror_token"),
             mirror_staking: String::from("mirror_staking"),
             mirror_mint: String::from("mirror_mint"),
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::contract::{execute, instantiate, query};
+    use crate::state::{read_harvest_bounty, read_short_position, read_state, store_state};
     use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
 
     use anchor_market::market::{
@@ -13,11 +14,21 @@ mod tests {
     };
     use cw20::Cw20ExecuteMsg;
     use athena::asset::{Asset, AssetInfo};
-    use athena::vault_strategy::{ExecuteMsg, InstantiateMsg, QueryMsg, State};
+    use athena::vault::ExecuteMsg as VaultExecuteMsg;
+    use athena::vault_strategy::{
+        BalancesResponse, ExecuteMsg, InstantiateMsg, MirrorBalanceInfo, MirrorPositionsResponse, NetDeltaInfo, NetDeltaResponse,
+        QueryMsg, ShortPositionInfo, ShortPositionsResponse, State,
+    };
+    use mirror_protocol::lock::ExecuteMsg as MirrorLockExecuteMsg;
+    use mirror_protocol::mint::{
+        Cw20HookMsg as MirrorMintCw20HookMsg, ExecuteMsg as MirrorMintExecuteMsg,
+        PositionResponse as MirrorPositionResponse,
+    };
     use mirror_protocol::staking::{
         ExecuteMsg as MirrorStakingExecuteMsg,
         RewardInfoResponseItem as MirrorStakingRewardInfoResponseItem,
     };
+    use terraswap::asset::{Asset as TerraswapAsset, AssetInfo as TerraswapAssetInfo};
     use terraswap::pair::Cw20HookMsg as TerraswapCw20HookMsg;
     use terraswap::pair::ExecuteMsg as TerraPairExecuteMsg;
 
@@ -87,12 +98,233 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: deposit_amount,
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: Uint128::zero(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
     }
 
+    #[test]
+    fn deploy_fails_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr", &[]),
+            ExecuteMsg::Deploy {},
+        )
+        .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn deploy_deposits_idle_balance_up_to_anchor_allocation() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: None,
+            anchor_allocation: Some(Decimal::percent(60u64)),
+            rebalance_band: None,
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: None,
+            harvest_bounty: None,
+            harvest_interval: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000000u64),
+            }],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(20000000u64))],
+            )],
+            &[],
+            &[],
+        );
+        deps.querier
+            .with_anchor_epoch_state(Decimal::one(), Uint128::zero());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("vault", &[]),
+            ExecuteMsg::Deploy {},
+        )
+        .unwrap();
+
+        // 60% of 120 UST total balance, 20 UST is already in anchor
+        let deposit_amount = Uint128::from(52000000u64);
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("anchor_market"),
+                funds: vec![Coin {
+                    denom: "uusd".into(),
+                    amount: deps.querier.deduct_tax(deposit_amount).unwrap(),
+                }],
+                msg: to_binary(&AnchorExecuteMsg::DepositStable {}).unwrap(),
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "deploy"), attr("amount", deposit_amount)]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+        assert_eq!(state.anchor_deposited, deposit_amount);
+    }
+
+    #[test]
+    fn rebalance_withdraws_anchor_above_band() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: None,
+            anchor_allocation: Some(Decimal::percent(20u64)),
+            rebalance_band: None,
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: None,
+            harvest_bounty: None,
+            harvest_interval: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(20000000u64),
+            }],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(80000000u64))],
+            )],
+            &[],
+            &[],
+        );
+        deps.querier
+            .with_anchor_epoch_state(Decimal::one(), Uint128::zero());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::Rebalance {},
+        )
+        .unwrap();
+
+        // 20% of 100 UST total balance, 80 UST is in anchor
+        let aterra_amount = Uint128::from(60000000u64);
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+                    aterra_amount: Some(aterra_amount),
+                })
+                .unwrap(),
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "rebalance"),
+                attr("withdraw_anchor", aterra_amount)
+            ]
+        );
+    }
+
+    #[test]
+    fn rebalance_does_nothing_within_band() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: None,
+            anchor_allocation: Some(Decimal::percent(60u64)),
+            rebalance_band: Some(Decimal::percent(5u64)),
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: None,
+            harvest_bounty: None,
+            harvest_interval: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(42000000u64),
+            }],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(58000000u64))],
+            )],
+            &[],
+            &[],
+        );
+        deps.querier
+            .with_anchor_epoch_state(Decimal::one(), Uint128::zero());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("worker", &[]),
+            ExecuteMsg::Rebalance {},
+        )
+        .unwrap();
+
+        assert_eq!(res.messages, vec![]);
+        assert_eq!(res.attributes, vec![attr("action", "rebalance")]);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr", &[]),
+            ExecuteMsg::Rebalance {},
+        )
+        .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
     #[test]
     fn deposit_anchor_several_times() {
         let mut deps = _instantiate_strategy();
@@ -128,7 +360,10 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: amount1 + amount2,
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: Uint128::zero(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
@@ -281,12 +516,110 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: deposit_amount.checked_sub(original_deposited).unwrap(),
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: (withdraw_amount * exchange_rate)
+                    .checked_sub(original_deposited + performance_fee)
+                    .unwrap(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
     }
 
+    #[test]
+    fn withdraw_anchor_sends_insurance_fee_to_vault() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: Some(Decimal::percent(20u64)),
+            anchor_allocation: None,
+            rebalance_band: None,
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: None,
+            harvest_bounty: None,
+            harvest_interval: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg).unwrap();
+
+        let deposit_amount = Uint128::from(100000000u64);
+
+        _deposit_anchor(&mut deps, deposit_amount);
+
+        let aterra_balance = Uint128::from(80000000u64);
+
+        let withdraw_amount = Uint128::from(50000000u64);
+
+        let exchange_rate = Decimal::percent(150u64);
+
+        let original_deposited =
+            deposit_amount * Decimal::from_ratio(withdraw_amount, aterra_balance);
+
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &aterra_balance)],
+            )],
+            &[],
+            &[],
+        );
+
+        deps.querier
+            .with_anchor_epoch_state(exchange_rate, Uint128::zero());
+
+        let performance_fee = (withdraw_amount * exchange_rate)
+            .checked_sub(original_deposited)
+            .unwrap()
+            * Decimal::percent(5u64);
+        let insurance_fee = performance_fee * Decimal::percent(20u64);
+
+        let msg = ExecuteMsg::WithdrawAnchor {
+            aterra_amount: Some(withdraw_amount),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("aterra_contract"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: String::from("anchor_market"),
+                        amount: withdraw_amount,
+                        msg: to_binary(&AnchorMarketCw20HookMsg::RedeemStable {}).unwrap(),
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("vault"),
+                    msg: to_binary(&VaultExecuteMsg::FundInsurance {}).unwrap(),
+                    funds: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps.querier.deduct_tax(insurance_fee).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("treasury"),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: deps
+                            .querier
+                            .deduct_tax(performance_fee - insurance_fee)
+                            .unwrap(),
+                    }],
+                })),
+            ]
+        );
+    }
+
     #[test]
     fn withdraw_anchor_by_itself() {
         let mut deps = _instantiate_strategy();
@@ -364,7 +697,12 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: deposit_amount.checked_sub(original_deposited).unwrap(),
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: (withdraw_amount * exchange_rate)
+                    .checked_sub(original_deposited + performance_fee)
+                    .unwrap(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
@@ -433,7 +771,10 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: deposit_amount.checked_sub(original_deposited).unwrap(),
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: Uint128::zero(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
@@ -511,7 +852,12 @@ mod tests {
         assert_eq!(
             State {
                 anchor_deposited: Uint128::zero(),
-                aterra_collateral: Uint128::zero()
+                aterra_collateral: Uint128::zero(),
+                high_water_mark: (aterra_balance * exchange_rate)
+                    .checked_sub(deposit_amount + performance_fee)
+                    .unwrap(),
+                last_harvest: 0u64,
+                vault_debt: Uint128::zero()
             },
             state
         );
@@ -524,6 +870,7 @@ mod tests {
         let msg = ExecuteMsg::DepositMirror {
             amount: Uint128::from(100000000u64),
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
         };
 
         let info = mock_info("addr", &[]);
@@ -539,6 +886,7 @@ mod tests {
         let msg = ExecuteMsg::DepositMirror {
             amount: Uint128::zero(),
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
         };
 
         let info = mock_info("worker", &[]);
@@ -563,10 +911,16 @@ mod tests {
                 String::from("m_liquidity_token"),
             ],
         )]);
+        deps.querier.with_token_info(
+            &[],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::percent(400u64))],
+        );
 
         let msg = ExecuteMsg::DepositMirror {
             amount: deposit_amount,
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
         };
 
         let info = mock_info("worker", &[]);
@@ -588,8 +942,8 @@ mod tests {
                         amount: half_amount
                     }],
                     msg: to_binary(&TerraPairExecuteMsg::Swap {
-                        belief_price: None,
-                        max_spread: None,
+                        belief_price: Some(Decimal::percent(400u64)),
+                        max_spread: Some(Decimal::percent(1u64)),
                         to: None,
                         offer_asset: Asset {
                             info: AssetInfo::NativeToken {
@@ -658,8 +1012,8 @@ mod tests {
                     (&String::from("m_apple_pair"), &pool_mirror_balance),
                 ],
             )],
-            &vec![],
-            &vec![],
+            &[(&String::from("m_liquidity_token"), &Uint128::from(1000000000u64))],
+            &[(&String::from("m_apple"), &Decimal::percent(1u64))],
         );
         deps.querier.with_balance(&[(
             &String::from("m_apple_pair"),
@@ -720,10 +1074,19 @@ mod tests {
                             }
                             .into(),
                         ],
-                        slippage_tolerance: None,
+                        slippage_tolerance: Some(Decimal::percent(1u64)),
                     })
                     .unwrap()
-                }))
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::RecordMirrorLpHook {
+                        mirror_asset_addr: String::from("m_apple"),
+                        prev_lp_amount: Uint128::zero(),
+                    })
+                    .unwrap(),
+                })),
             ]
         );
         assert_eq!(
@@ -736,25 +1099,61 @@ mod tests {
     }
 
     #[test]
-    fn withdraw_mirror_fails_if_unauthorized() {
+    fn deposit_mirror_hook_fails_if_pool_price_deviates_from_oracle() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::WithdrawMirror {
-            mirror_lp_amount: Uint128::from(10000000u64),
+        let pool_mirror_balance = Uint128::from(10000000000000u64);
+        let pool_stable_balance = Uint128::from(100000000000u64);
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
+                &[
+                    (&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(10000000u64)),
+                    (&String::from("m_apple_pair"), &pool_mirror_balance),
+                ],
+            )],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::permille(11u64))],
+        );
+        deps.querier.with_balance(&[(
+            &String::from("m_apple_pair"),
+            &[Coin {
+                denom: "uusd".into(),
+                amount: pool_stable_balance,
+            }],
+        )]);
+
+        let msg = ExecuteMsg::DepositMirrorHook {
             mirror_asset_addr: String::from("m_apple"),
         };
 
-        let info = mock_info("addr", &[]);
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
 
+        // Pool price is about 9% below the oracle price
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
-
-        assert_eq!(res, StdError::generic_err("unauthorized"));
+        assert_eq!(
+            res,
+            StdError::generic_err("Pool price deviates from oracle price")
+        );
     }
 
     #[test]
-    fn withdraw_mirror_by_worker() {
+    fn mirror_positions_track_cost_and_realized_proceeds() {
         let mut deps = _instantiate_strategy();
 
+        let mirror_token_balance = Uint128::from(10000000u64);
+        let pool_mirror_balance = Uint128::from(10000000000000u64);
+        let pool_stable_balance = Uint128::from(100000000000u64);
+        let lp_supply = Uint128::from(1000000000u64);
+
         deps.querier.with_mirror_asset(&[(
             &String::from("m_apple"),
             &[
@@ -762,24 +1161,156 @@ mod tests {
                 String::from("m_liquidity_token"),
             ],
         )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
+                &[
+                    (&String::from(MOCK_CONTRACT_ADDR), &mirror_token_balance),
+                    (&String::from("m_apple_pair"), &pool_mirror_balance),
+                ],
+            )],
+            &[(&String::from("m_liquidity_token"), &lp_supply)],
+            &[(&String::from("m_apple"), &Decimal::percent(1u64))],
+        );
+        deps.querier.with_balance(&[(
+            &String::from("m_apple_pair"),
+            &[Coin {
+                denom: "uusd".into(),
+                amount: pool_stable_balance,
+            }],
+        )]);
 
-        let withdraw_lp_amount = Uint128::from(10000000u64);
-
-        let msg = ExecuteMsg::WithdrawMirror {
-            mirror_lp_amount: withdraw_lp_amount,
+        let msg = ExecuteMsg::DepositMirrorHook {
             mirror_asset_addr: String::from("m_apple"),
         };
-
-        let info = mock_info("worker", &[]);
-
-        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
-
-        assert_eq!(
-            res.messages,
-            vec![
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from("mirror_staking"),
-                    funds: vec![],
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let cost = mirror_token_balance * Decimal::from_ratio(pool_stable_balance, pool_mirror_balance);
+
+        // Auto stake bonded the LP minted by terraswap
+        let lp_amount = Uint128::from(98010u64);
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: lp_amount,
+            pending_reward: Uint128::zero(),
+            is_short: false,
+        }]);
+        let msg = ExecuteMsg::RecordMirrorLpHook {
+            mirror_asset_addr: String::from("m_apple"),
+            prev_lp_amount: Uint128::zero(),
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res: MirrorPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MirrorPositions {}).unwrap())
+                .unwrap();
+        assert_eq!(res.positions.len(), 1);
+        assert_eq!(res.positions[0].mirror_asset, String::from("m_apple"));
+        assert_eq!(res.positions[0].cost, cost);
+        assert_eq!(res.positions[0].lp_amount, lp_amount);
+        assert_eq!(res.positions[0].realized_cost, Uint128::zero());
+
+        // Withdrawal returned 60000 UST and 1000000 m_apple worth 10000 UST
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".into(),
+                amount: Uint128::from(61000u64),
+            }],
+        )]);
+        let withdraw_lp_amount = lp_amount.multiply_ratio(1u128, 2u128);
+        let msg = ExecuteMsg::RealizeMirrorLpHook {
+            mirror_asset_addr: String::from("m_apple"),
+            lp_amount: withdraw_lp_amount,
+            prev_stable_balance: Uint128::from(1000u64),
+            prev_asset_balance: mirror_token_balance - Uint128::from(1000000u64),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "realize_mirror_lp_hook"),
+                attr("lp_amount", withdraw_lp_amount),
+                attr("proceeds", "70000"),
+            ]
+        );
+
+        let res: MirrorPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MirrorPositions {}).unwrap())
+                .unwrap();
+        let position = &res.positions[0];
+        let withdrawn_cost = cost.multiply_ratio(withdraw_lp_amount, lp_amount);
+        assert_eq!(position.lp_amount, lp_amount - withdraw_lp_amount);
+        assert_eq!(position.cost, cost - withdrawn_cost);
+        assert_eq!(position.realized_cost, withdrawn_cost);
+        assert_eq!(position.realized_proceeds, Uint128::from(70000u64));
+        assert_eq!(
+            position.profit,
+            (position.value + position.realized_proceeds).saturating_sub(cost)
+        );
+    }
+
+    #[test]
+    fn withdraw_mirror_fails_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::WithdrawMirror {
+            mirror_lp_amount: Uint128::from(10000000u64),
+            mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
+        };
+
+        let info = mock_info("addr", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn withdraw_mirror_by_worker() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
+
+        let withdraw_lp_amount = Uint128::from(10000000u64);
+
+        let msg = ExecuteMsg::WithdrawMirror {
+            mirror_lp_amount: withdraw_lp_amount,
+            mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mirror_staking"),
+                    funds: vec![],
                     msg: to_binary(&MirrorStakingExecuteMsg::Unbond {
                         asset_token: String::from("m_apple"),
                         amount: withdraw_lp_amount,
@@ -801,9 +1332,21 @@ mod tests {
                     funds: vec![],
                     msg: to_binary(&ExecuteMsg::WithdrawMirrorHook {
                         mirror_asset_addr: String::from("m_apple"),
+                        max_spread: None,
                     })
                     .unwrap(),
-                }))
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::RealizeMirrorLpHook {
+                        mirror_asset_addr: String::from("m_apple"),
+                        lp_amount: withdraw_lp_amount,
+                        prev_stable_balance: Uint128::zero(),
+                        prev_asset_balance: Uint128::zero(),
+                    })
+                    .unwrap(),
+                })),
             ]
         );
         assert_eq!(
@@ -826,12 +1369,21 @@ mod tests {
                 String::from("m_liquidity_token"),
             ],
         )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
 
         let withdraw_lp_amount = Uint128::from(10000000u64);
 
         let msg = ExecuteMsg::WithdrawMirror {
             mirror_lp_amount: withdraw_lp_amount,
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
         };
 
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
@@ -865,9 +1417,21 @@ mod tests {
                     funds: vec![],
                     msg: to_binary(&ExecuteMsg::WithdrawMirrorHook {
                         mirror_asset_addr: String::from("m_apple"),
+                        max_spread: None,
                     })
                     .unwrap(),
-                }))
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::RealizeMirrorLpHook {
+                        mirror_asset_addr: String::from("m_apple"),
+                        lp_amount: withdraw_lp_amount,
+                        prev_stable_balance: Uint128::zero(),
+                        prev_asset_balance: Uint128::zero(),
+                    })
+                    .unwrap(),
+                })),
             ]
         );
         assert_eq!(
@@ -885,6 +1449,7 @@ mod tests {
 
         let msg = ExecuteMsg::WithdrawMirrorHook {
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: None,
         };
 
         let info = mock_info("worker", &[]);
@@ -912,12 +1477,13 @@ mod tests {
                 &String::from("m_apple"),
                 &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_token_balance)],
             )],
-            &vec![],
-            &vec![],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::percent(400u64))],
         );
 
         let msg = ExecuteMsg::WithdrawMirrorHook {
             mirror_asset_addr: String::from("m_apple"),
+            max_spread: Some(Decimal::percent(2u64)),
         };
 
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
@@ -933,8 +1499,8 @@ mod tests {
                     amount: mirror_token_balance,
                     contract: String::from("m_apple_pair"),
                     msg: to_binary(&TerraswapCw20HookMsg::Swap {
-                        belief_price: None,
-                        max_spread: None,
+                        belief_price: Some(Decimal::percent(25u64)),
+                        max_spread: Some(Decimal::percent(2u64)),
                         to: None,
                     })
                     .unwrap()
@@ -949,7 +1515,7 @@ mod tests {
     fn compound_mirror_fails_if_unauthorized() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::CompoundMirror {};
+        let msg = ExecuteMsg::CompoundMirror { max_spread: None };
 
         let info = mock_info("addr", &[]);
 
@@ -962,7 +1528,7 @@ mod tests {
     fn compound_mirror_by_worker() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::CompoundMirror {};
+        let msg = ExecuteMsg::CompoundMirror { max_spread: None };
 
         let info = mock_info("worker", &[]);
 
@@ -980,7 +1546,7 @@ mod tests {
                 SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: String::from(MOCK_CONTRACT_ADDR),
                     funds: vec![],
-                    msg: to_binary(&ExecuteMsg::CompoundMirrorHook {}).unwrap(),
+                    msg: to_binary(&ExecuteMsg::CompoundMirrorHook { max_spread: None }).unwrap(),
                 })),
             ]
         );
@@ -991,7 +1557,7 @@ mod tests {
     fn compound_mirror_by_itself() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::CompoundMirror {};
+        let msg = ExecuteMsg::CompoundMirror { max_spread: None };
 
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
 
@@ -1009,18 +1575,83 @@ mod tests {
                 SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: String::from(MOCK_CONTRACT_ADDR),
                     funds: vec![],
-                    msg: to_binary(&ExecuteMsg::CompoundMirrorHook {}).unwrap(),
+                    msg: to_binary(&ExecuteMsg::CompoundMirrorHook { max_spread: None }).unwrap(),
                 })),
             ]
         );
         assert_eq!(res.attributes, vec![attr("action", "compound_mirror"),]);
     }
 
+    #[test]
+    fn compound_mirror_tracks_short_rewards() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_reward_info(vec![
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_apple"),
+                bond_amount: Uint128::from(100000u64),
+                pending_reward: Uint128::from(1000u64),
+                is_short: false,
+            },
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_apple"),
+                bond_amount: Uint128::from(10000u64),
+                pending_reward: Uint128::from(5000u64),
+                is_short: true,
+            },
+        ]);
+
+        let msg = ExecuteMsg::CompoundMirror { max_spread: None };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "compound_mirror"),
+                attr("short_reward", "5000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn total_balance_includes_pending_short_rewards() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("mirror_token"),
+            &[
+                String::from("mirror_token_pair"),
+                String::from("mirror_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[(&String::from("mirror_token"), &Decimal::percent(200u64))],
+        );
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: Uint128::from(10000u64),
+            pending_reward: Uint128::from(5000u64),
+            is_short: true,
+        }]);
+
+        let res: Uint128 =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap())
+                .unwrap();
+        assert_eq!(res, Uint128::from(10000u64));
+    }
+
     #[test]
     fn compound_mirror_hook_fails_if_unauthorized() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::CompoundMirrorHook {};
+        let msg = ExecuteMsg::CompoundMirrorHook { max_spread: None };
 
         let info = mock_info("addr", &[]);
 
@@ -1045,18 +1676,24 @@ mod tests {
         let mirror_price =
             Decimal::from_ratio(Uint128::from(100000000u64), Uint128::from(10000000u64));
         deps.querier.with_token_info(
-            &[(
-                &String::from("mirror_token"),
-                &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_balance)],
-            )],
+            &[
+                (
+                    &String::from("mirror_token"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_balance)],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
             &vec![],
             &[(&String::from("mirror_token"), &mirror_price)],
         );
 
-        let return_amount = mirror_balance * mirror_price;
+        let return_amount = deps.querier.deduct_tax(mirror_balance * mirror_price).unwrap();
         let performance_fee = return_amount * Decimal::percent(5u64);
 
-        let msg = ExecuteMsg::CompoundMirrorHook {};
+        let msg = ExecuteMsg::CompoundMirrorHook { max_spread: None };
 
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
 
@@ -1073,7 +1710,7 @@ mod tests {
                         contract: String::from("ust_mir_pair"),
                         msg: to_binary(&TerraswapCw20HookMsg::Swap {
                             belief_price: None,
-                            max_spread: None,
+                            max_spread: Some(Decimal::percent(1u64)),
                             to: None,
                         })
                         .unwrap()
@@ -1086,13 +1723,138 @@ mod tests {
                         denom: "uusd".into(),
                         amount: deps.querier.deduct_tax(performance_fee.clone()).unwrap(),
                     },],
-                }))
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("vault"),
+                    msg: to_binary(&VaultExecuteMsg::Report {
+                        gain: return_amount - performance_fee,
+                        loss: Uint128::zero(),
+                        debt_payment: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })),
             ]
         );
         assert_eq!(
             res.attributes,
             vec![attr("performance_fee", performance_fee)]
         );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+        assert_eq!(state.high_water_mark, return_amount - performance_fee);
+    }
+
+    #[test]
+    fn compound_mirror_hook_no_fee_below_high_water_mark() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("mirror_token"),
+            &[
+                String::from("ust_mir_pair"),
+                String::from("ust_mir_liquidity_token"),
+            ],
+        )]);
+
+        let mirror_balance = Uint128::from(10000000u64);
+        let mirror_price =
+            Decimal::from_ratio(Uint128::from(100000000u64), Uint128::from(10000000u64));
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("mirror_token"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_balance)],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
+            &[],
+            &[(&String::from("mirror_token"), &mirror_price)],
+        );
+
+        // Strategy is still below its debt to the vault, so there is no net gain
+        let return_amount = deps.querier.deduct_tax(mirror_balance * mirror_price).unwrap();
+        deps.querier.with_strategy_debt(return_amount * Uint128::from(2u64));
+
+        let msg = ExecuteMsg::CompoundMirrorHook { max_spread: None };
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("vault"),
+                msg: to_binary(&VaultExecuteMsg::Report {
+                    gain: return_amount,
+                    loss: Uint128::zero(),
+                    debt_payment: Uint128::zero(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))
+        );
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.attributes, vec![attr("performance_fee", "0")]);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+        assert_eq!(state.high_water_mark, Uint128::zero());
+    }
+
+    #[test]
+    fn compound_mirror_hook_uses_last_known_debt_if_unregistered() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("mirror_token"),
+            &[
+                String::from("ust_mir_pair"),
+                String::from("ust_mir_liquidity_token"),
+            ],
+        )]);
+
+        let mirror_balance = Uint128::from(10000000u64);
+        let mirror_price =
+            Decimal::from_ratio(Uint128::from(100000000u64), Uint128::from(10000000u64));
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("mirror_token"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_balance)],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
+            &[],
+            &[(&String::from("mirror_token"), &mirror_price)],
+        );
+
+        // Debt lookup fails, the cached debt still covers the whole balance
+        let return_amount = mirror_balance * mirror_price;
+        deps.querier.with_strategy_unregistered();
+        let mut state = read_state(&deps.storage).unwrap();
+        state.vault_debt = return_amount * Uint128::from(2u64);
+        store_state(&mut deps.storage, &state).unwrap();
+
+        let msg = ExecuteMsg::CompoundMirrorHook { max_spread: None };
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.attributes, vec![attr("performance_fee", "0")]);
+
+        let state = read_state(&deps.storage).unwrap();
+        assert_eq!(state.high_water_mark, Uint128::zero());
+        assert_eq!(state.vault_debt, return_amount * Uint128::from(2u64));
     }
 
     #[test]
@@ -1120,7 +1882,7 @@ mod tests {
             &[(&String::from("mirror_token"), &mirror_price)],
         );
 
-        let msg = ExecuteMsg::CompoundMirrorHook {};
+        let msg = ExecuteMsg::CompoundMirrorHook { max_spread: None };
 
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
 
@@ -1131,10 +1893,10 @@ mod tests {
     }
 
     #[test]
-    fn withdraw_all_falls_if_unauthorized() {
+    fn compound_anchor_fails_if_unauthorized() {
         let mut deps = _instantiate_strategy();
 
-        let msg = ExecuteMsg::WithdrawAll {};
+        let msg = ExecuteMsg::CompoundAnchor { max_spread: None };
 
         let info = mock_info("addr", &[]);
 
@@ -1144,39 +1906,738 @@ mod tests {
     }
 
     #[test]
-    fn withdraw_all_when_only_anchor_invested() {
+    fn compound_anchor_by_worker() {
         let mut deps = _instantiate_strategy();
 
-        deps.querier.with_mirror_asset(&[
-            (
-                &String::from("m_apple"),
-                &[
-                    String::from("m_apple_pair"),
-                    String::from("m_apple_liquidity_token"),
-                ],
-            ),
-            (
-                &String::from("m_google"),
-                &[
-                    String::from("m_google_pair"),
-                    String::from("m_google_liquidity_token"),
-                ],
-            ),
-            (
-                &String::from("m_luna"),
-                &[
-                    String::from("m_luna_pair"),
-                    String::from("m_luna_liquidity_token"),
-                ],
-            ),
-        ]);
-
-        let msg = ExecuteMsg::WithdrawAll {};
+        let msg = ExecuteMsg::CompoundAnchor { max_spread: Some(Decimal::percent(2u64)) };
 
         let info = mock_info("worker", &[]);
 
-        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("anchor_market"),
+                    funds: vec![],
+                    msg: to_binary(&AnchorExecuteMsg::ClaimRewards { to: None }).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundAnchorHook {
+                        max_spread: Some(Decimal::percent(2u64)),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(res.attributes, vec![attr("action", "compound_anchor")]);
+    }
+
+    #[test]
+    fn compound_anchor_hook_fails_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::CompoundAnchorHook { max_spread: None };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn compound_anchor_hook_by_itself() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[(
+            &String::from("anchor_token"),
+            &[
+                String::from("ust_anc_pair"),
+                String::from("ust_anc_liquidity_token"),
+            ],
+        )]);
+
+        let anchor_token_balance = Uint128::from(1000000u64);
+        let anchor_token_price = Decimal::percent(200u64);
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("anchor_token"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &anchor_token_balance)],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
+            &[],
+            &[(&String::from("anchor_token"), &anchor_token_price)],
+        );
+
+        let return_amount = deps
+            .querier
+            .deduct_tax(anchor_token_balance * anchor_token_price)
+            .unwrap();
+        let performance_fee = return_amount * Decimal::percent(5u64);
+        let deposit_amount = return_amount - performance_fee;
+
+        let msg = ExecuteMsg::CompoundAnchorHook { max_spread: Some(Decimal::percent(3u64)) };
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("anchor_token"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        amount: anchor_token_balance,
+                        contract: String::from("ust_anc_pair"),
+                        msg: to_binary(&TerraswapCw20HookMsg::Swap {
+                            belief_price: None,
+                            max_spread: Some(Decimal::percent(3u64)),
+                            to: None,
+                        })
+                        .unwrap()
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("treasury"),
+                    amount: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: deps.querier.deduct_tax(performance_fee).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("anchor_market"),
+                    funds: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: deps.querier.deduct_tax(deposit_amount).unwrap(),
+                    }],
+                    msg: to_binary(&AnchorExecuteMsg::DepositStable {}).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("vault"),
+                    msg: to_binary(&VaultExecuteMsg::Report {
+                        gain: deposit_amount,
+                        loss: Uint128::zero(),
+                        debt_payment: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("anchor_token_amount", anchor_token_balance),
+                attr("performance_fee", performance_fee),
+                attr("deposit_amount", deposit_amount),
+            ]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+        assert_eq!(state.anchor_deposited, deposit_amount);
+        assert_eq!(state.high_water_mark, deposit_amount);
+    }
+
+    #[test]
+    fn compound_anchor_hook_nothing_if_no_anc_balance() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_token_info(
+            &[(
+                &String::from("anchor_token"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
+
+        let msg = ExecuteMsg::CompoundAnchorHook { max_spread: None };
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(res.messages, vec![]);
+    }
+
+    #[test]
+    fn harvest_by_anyone_pays_bounty_from_proceeds() {
+        let mut deps = _instantiate_strategy();
+        _set_harvest_bounty(&mut deps, Uint128::from(1000000u64));
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: Uint128::from(1000u64),
+            pending_reward: Uint128::from(10000000u64),
+            is_short: false,
+        }]);
+        deps.querier
+            .with_anchor_pending_rewards(Decimal::from_ratio(2000000u64, 1u64));
+
+        let env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Harvest {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundMirror { max_spread: None }).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundAnchor { max_spread: None }).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::HarvestHook {}).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::Deploy {}).unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "harvest"),
+                attr("mirror_reward", "10000000"),
+                attr("anchor_reward", "2000000"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+        let state: State = from_binary(&res).unwrap();
+        assert_eq!(state.last_harvest, env.block.time.seconds());
+
+        // Bounty is paid out of the compounded profit and not reported as gain
+        let mirror_balance = Uint128::from(10000000u64);
+        deps.querier.with_reward_info(vec![]);
+        _with_mirror_reward(&mut deps, mirror_balance);
+        let return_amount = deps
+            .querier
+            .deduct_tax(mirror_balance * Decimal::percent(1000u64))
+            .unwrap();
+        let performance_fee = return_amount * Decimal::percent(5u64);
+        let harvest_bounty = Uint128::from(1000000u64);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::CompoundMirrorHook { max_spread: None },
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages[2..],
+            vec![
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("keeper"),
+                    amount: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: deps.querier.deduct_tax(harvest_bounty).unwrap(),
+                    }],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("vault"),
+                    msg: to_binary(&VaultExecuteMsg::Report {
+                        gain: return_amount - performance_fee - harvest_bounty,
+                        loss: Uint128::zero(),
+                        debt_payment: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("performance_fee", performance_fee),
+                attr("harvest_bounty", harvest_bounty),
+            ]
+        );
+
+        // Whole bounty is paid, so later compounding keeps its profit
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::CompoundMirrorHook { max_spread: None },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 3);
+    }
+
+    #[test]
+    fn harvest_bounty_is_capped_at_profit() {
+        let mut deps = _instantiate_strategy();
+        _set_harvest_bounty(&mut deps, Uint128::from(1000000u64));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Harvest {},
+        )
+        .unwrap();
+
+        let mirror_balance = Uint128::from(10000u64);
+        _with_mirror_reward(&mut deps, mirror_balance);
+        let return_amount = deps
+            .querier
+            .deduct_tax(mirror_balance * Decimal::percent(1000u64))
+            .unwrap();
+        let performance_fee = return_amount * Decimal::percent(5u64);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::CompoundMirrorHook { max_spread: None },
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("performance_fee", performance_fee),
+                attr("harvest_bounty", return_amount - performance_fee),
+            ]
+        );
+        assert_eq!(
+            res.messages.last().unwrap(),
+            &SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("vault"),
+                msg: to_binary(&VaultExecuteMsg::Report {
+                    gain: Uint128::zero(),
+                    loss: Uint128::zero(),
+                    debt_payment: Uint128::zero(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))
+        );
+
+        // No anchor reward, so the rest of the bounty is dropped by the hook
+        deps.querier.with_token_info(
+            &[(
+                &String::from("anchor_token"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::CompoundAnchorHook { max_spread: None },
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![]);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::HarvestHook {},
+        )
+        .unwrap();
+        assert_eq!(read_harvest_bounty(&deps.storage).unwrap(), None);
+    }
+
+    #[test]
+    fn harvest_hook_fails_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::HarvestHook {},
+        )
+        .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn harvest_fails_within_interval() {
+        let mut deps = _instantiate_strategy();
+
+        let mut env = mock_env();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Harvest {},
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(86399u64);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Harvest {},
+        )
+        .unwrap_err();
+        assert_eq!(res, StdError::generic_err("Harvest interval not elapsed"));
+
+        env.block.time = env.block.time.plus_seconds(1u64);
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), ExecuteMsg::Harvest {})
+            .unwrap();
+        assert_eq!(res.messages.len(), 2);
+    }
+
+    #[test]
+    fn harvest_skips_compounding_without_pending_rewards() {
+        let mut deps = _instantiate_strategy();
+        deps.querier
+            .with_anchor_pending_rewards(Decimal::from_ratio(1000u64, 1u64));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Harvest {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundAnchor { max_spread: None }).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::HarvestHook {}).unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::Deploy {}).unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "harvest"),
+                attr("mirror_reward", "0"),
+                attr("anchor_reward", "1000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn deploy_by_itself() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[],
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::Deploy {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "deploy"), attr("amount", "0")]
+        );
+    }
+
+    #[test]
+    fn withdraw_all_falls_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::WithdrawAll {};
+
+        let info = mock_info("addr", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn withdraw_all_when_only_anchor_invested() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[
+            (
+                &String::from("m_apple"),
+                &[
+                    String::from("m_apple_pair"),
+                    String::from("m_apple_liquidity_token"),
+                ],
+            ),
+            (
+                &String::from("m_google"),
+                &[
+                    String::from("m_google_pair"),
+                    String::from("m_google_liquidity_token"),
+                ],
+            ),
+            (
+                &String::from("m_luna"),
+                &[
+                    String::from("m_luna_pair"),
+                    String::from("m_luna_liquidity_token"),
+                ],
+            ),
+        ]);
+
+        let msg = ExecuteMsg::WithdrawAll {};
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+                        aterra_amount: None
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundMirror { max_spread: None }).unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(res.attributes, vec![attr("action", "withdraw_all"),]);
+    }
+
+    #[test]
+    fn withdraw_all_both_anchor_and_mirror() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[
+            (
+                &String::from("m_apple"),
+                &[
+                    String::from("m_apple_pair"),
+                    String::from("m_apple_liquidity_token"),
+                ],
+            ),
+            (
+                &String::from("m_google"),
+                &[
+                    String::from("m_google_pair"),
+                    String::from("m_google_liquidity_token"),
+                ],
+            ),
+            (
+                &String::from("m_luna"),
+                &[
+                    String::from("m_luna_pair"),
+                    String::from("m_luna_liquidity_token"),
+                ],
+            ),
+        ]);
+
+        deps.querier.with_reward_info(vec![
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_apple"),
+                bond_amount: Uint128::from(100000000u64),
+                pending_reward: Uint128::from(100000u64),
+                is_short: false,
+            },
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_google"),
+                bond_amount: Uint128::from(110000000u64),
+                pending_reward: Uint128::from(110000u64),
+                is_short: false,
+            },
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_luna"),
+                bond_amount: Uint128::from(120000000u64),
+                pending_reward: Uint128::from(120000u64),
+                is_short: false,
+            },
+        ]);
+
+        let msg = ExecuteMsg::WithdrawAll {};
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawAnchor {
+                        aterra_amount: None
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                        mirror_lp_amount: Uint128::from(100000000u64),
+                        mirror_asset_addr: String::from("m_apple"),
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                        mirror_lp_amount: Uint128::from(110000000u64),
+                        mirror_asset_addr: String::from("m_google"),
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                        mirror_lp_amount: Uint128::from(120000000u64),
+                        mirror_asset_addr: String::from("m_luna"),
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::CompoundMirror { max_spread: None }).unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(res.attributes, vec![attr("action", "withdraw_all"),]);
+    }
+
+    #[test]
+    fn withdraw_invested_fails_if_unauthorized() {
+        let mut deps = _instantiate_strategy();
+
+        let msg = ExecuteMsg::WithdrawInvested {
+            amount: Uint128::from(1000u64),
+        };
+
+        let info = mock_info("addr", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn withdraw_invested_from_idle_balance() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000u64),
+            }],
+        )]);
+
+        let msg = ExecuteMsg::WithdrawInvested {
+            amount: Uint128::from(500u64),
+        };
+
+        let info = mock_info("vault", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::WithdrawToVault {
+                    amount: Some(Uint128::from(500u64)),
+                })
+                .unwrap(),
+            }))]
+        );
+    }
+
+    #[test]
+    fn withdraw_invested_redeems_anchor_first() {
+        let mut deps = _instantiate_strategy();
+        _deposit_anchor(&mut deps, Uint128::from(1000u64));
+
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100u64),
+            }],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1000u64))],
+            )],
+            &[],
+            &[],
+        );
+        deps.querier
+            .with_anchor_epoch_state(Decimal::percent(110u64), Uint128::zero());
+
+        let msg = ExecuteMsg::WithdrawInvested {
+            amount: Uint128::from(600u64),
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 1100 UST in anchor is worth 1095 net of 5% fee on 100 UST gain,
+        // 500 UST is freed by 457 aUST
         assert_eq!(
             res.messages,
             vec![
@@ -1184,124 +2645,1269 @@ mod tests {
                     contract_addr: String::from(MOCK_CONTRACT_ADDR),
                     funds: vec![],
                     msg: to_binary(&ExecuteMsg::WithdrawAnchor {
-                        aterra_amount: None
+                        aterra_amount: Some(Uint128::from(457u64)),
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawInvestedHook {
+                        amount: Uint128::from(600u64),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "withdraw_invested"),
+                attr("amount", "600"),
+                attr("aterra_amount", "457"),
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_invested_unwinds_mirror_proportionally() {
+        let mut deps = _instantiate_strategy();
+
+        deps.querier.with_mirror_asset(&[
+            (
+                &String::from("m_apple"),
+                &[
+                    String::from("m_apple_pair"),
+                    String::from("m_apple_liquidity_token"),
+                ],
+            ),
+            (
+                &String::from("m_google"),
+                &[
+                    String::from("m_google_pair"),
+                    String::from("m_google_liquidity_token"),
+                ],
+            ),
+        ]);
+        deps.querier.with_balance(&[
+            (
+                &String::from("m_apple_pair"),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000000000u64),
+                }],
+            ),
+            (
+                &String::from("m_google_pair"),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000000000u64),
+                }],
+            ),
+        ]);
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+                (
+                    &String::from("m_apple"),
+                    &[(&String::from("m_apple_pair"), &Uint128::from(10000000u64))],
+                ),
+                (
+                    &String::from("m_google"),
+                    &[(&String::from("m_google_pair"), &Uint128::from(10000000u64))],
+                ),
+            ],
+            &[
+                (
+                    &String::from("m_apple_liquidity_token"),
+                    &Uint128::from(1000000000u64),
+                ),
+                (
+                    &String::from("m_google_liquidity_token"),
+                    &Uint128::from(1000000000u64),
+                ),
+            ],
+            &[
+                (&String::from("m_apple"), &Decimal::percent(10000u64)),
+                (&String::from("m_google"), &Decimal::percent(10000u64)),
+            ],
+        );
+        deps.querier.with_reward_info(vec![
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_apple"),
+                bond_amount: Uint128::from(500000000u64),
+                pending_reward: Uint128::zero(),
+                is_short: false,
+            },
+            MirrorStakingRewardInfoResponseItem {
+                asset_token: String::from("m_google"),
+                bond_amount: Uint128::from(250000000u64),
+                pending_reward: Uint128::zero(),
+                is_short: false,
+            },
+        ]);
+
+        // Liquidity is worth 999 UST of m_apple and 499 UST of m_google after tax
+        let msg = ExecuteMsg::WithdrawInvested {
+            amount: Uint128::from(749000000u64),
+        };
+        let info = mock_info("worker", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                        mirror_lp_amount: Uint128::from(250000000u64),
+                        mirror_asset_addr: String::from("m_apple"),
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
+                        mirror_lp_amount: Uint128::from(125000000u64),
+                        mirror_asset_addr: String::from("m_google"),
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawInvestedHook {
+                        amount: Uint128::from(749000000u64),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+
+        let msg = ExecuteMsg::WithdrawInvested {
+            amount: Uint128::from(1500000000u64),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, StdError::generic_err("Not enough invested balance"));
+    }
+
+    #[test]
+    fn withdraw_invested_hook_sends_idle_balance_if_short() {
+        let mut deps = _instantiate_strategy();
+
+        // Unwinding returned 995 UST of the 1000 UST sized
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(995u64),
+            }],
+        )]);
+
+        let msg = ExecuteMsg::WithdrawInvestedHook {
+            amount: Uint128::from(1000u64),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg)
+            .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::WithdrawToVault {
+                    amount: Some(Uint128::from(995u64)),
+                })
+                .unwrap(),
+            }))]
+        );
+    }
+
+    #[test]
+    fn open_short_position_registers_position() {
+        let mut deps = _instantiate_strategy();
+
+        let aterra_amount = Uint128::from(1000000u64);
+        let msg = ExecuteMsg::OpenShortPosition {
+            aterra_amount,
+            collateral_ratio: Decimal::percent(200u64),
+            mirror_asset_addr: String::from("m_apple"),
+            belief_price: None,
+            max_spread: None,
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "open_short_position"),
+                attr("position_idx", "1"),
+                attr("aterra_amount", aterra_amount),
+                attr("collateral_ratio", "2"),
+            ]
+        );
+
+        // Collateral grew with aUST interest and asset price halved
+        deps.querier.with_mirror_positions(
+            vec![MirrorPositionResponse {
+                idx: Uint128::from(1u64),
+                owner: String::from(MOCK_CONTRACT_ADDR),
+                collateral: TerraswapAsset {
+                    info: TerraswapAssetInfo::Token {
+                        contract_addr: String::from("aterra_contract"),
+                    },
+                    amount: aterra_amount,
+                },
+                asset: TerraswapAsset {
+                    info: TerraswapAssetInfo::Token {
+                        contract_addr: String::from("m_apple"),
+                    },
+                    amount: Uint128::from(10000u64),
+                },
+                is_short: true,
+            }],
+            Decimal::percent(150u64),
+        );
+        deps.querier
+            .with_anchor_epoch_state(Decimal::percent(120u64), Uint128::from(1000000u64));
+        deps.querier.with_token_info(
+            &[],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::percent(5000u64))],
+        );
+
+        let res: ShortPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ShortPositions {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res.positions,
+            vec![ShortPositionInfo {
+                position_idx: Uint128::from(1u64),
+                mirror_asset: String::from("m_apple"),
+                opening_collateral: aterra_amount,
+                opening_collateral_ratio: Decimal::percent(200u64),
+                opened_at: mock_env().block.time.seconds(),
+                collateral: aterra_amount,
+                asset_amount: Uint128::from(10000u64),
+                collateral_ratio: Decimal::percent(240u64),
+                min_collateral_ratio: Decimal::percent(150u64),
+                liquidation_distance: Decimal::percent(90u64),
+            }]
+        );
+    }
+
+    #[test]
+    fn check_short_position_fails_if_healthy() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let msg = ExecuteMsg::CheckShortPosition {
+            position_idx: Uint128::from(1u64),
+        };
+
+        let info = mock_info("keeper", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, StdError::generic_err("Short position is healthy"));
+    }
+
+    #[test]
+    fn check_short_position_deposits_collateral_and_pays_bounty() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(6000u64));
+
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: None,
+            anchor_allocation: None,
+            rebalance_band: None,
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: Some(Uint128::from(1000000u64)),
+            harvest_bounty: None,
+            harvest_interval: None,
+        };
+        let info = mock_info("governance", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let mut state = read_state(deps.as_ref().storage).unwrap();
+        state.anchor_deposited = Uint128::from(1200000u64);
+        store_state(deps.as_mut().storage, &state).unwrap();
+
+        let msg = ExecuteMsg::CheckShortPosition {
+            position_idx: Uint128::from(1u64),
+        };
+
+        let info = mock_info("keeper", &[]);
+
+        // Collateral ratio is 166%, restore it to 250%
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let deposit_amount = Uint128::from(500000u64);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("aterra_contract"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: String::from("mirror_mint"),
+                        amount: deposit_amount,
+                        msg: to_binary(&MirrorMintCw20HookMsg::Deposit {
+                            position_idx: Uint128::from(1u64),
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("keeper"),
+                    amount: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: deps.querier.deduct_tax(Uint128::from(1000000u64)).unwrap(),
+                    }],
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "check_short_position"),
+                attr("position_idx", "1"),
+                attr("deposit_collateral", deposit_amount),
+                attr("keeper_bounty", "1000000"),
+            ]
+        );
+
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(state.aterra_collateral, Uint128::from(1500000u64));
+        // Half of the aUST balance moved to the collateral
+        assert_eq!(state.anchor_deposited, Uint128::from(600000u64));
+
+        let res: ShortPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ShortPositions {}).unwrap())
+                .unwrap();
+        assert_eq!(res.positions[0].opening_collateral, Uint128::from(1500000u64));
+    }
+
+    #[test]
+    fn check_short_position_burns_with_default_max_spread() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(6000u64));
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        // No idle aUST to top up the collateral
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+            )],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::percent(6000u64))],
+        );
+
+        let msg = ExecuteMsg::CheckShortPosition {
+            position_idx: Uint128::from(1u64),
+        };
+
+        let info = mock_info("keeper", &[]);
+
+        // Collateral ratio is 166%, burn down to 250%
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let burn_amount = Uint128::from(3333u64);
+        let offer_amount = burn_amount * Decimal::percent(6000u64);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("m_apple_pair"),
+                    funds: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: offer_amount,
+                    }],
+                    msg: to_binary(&TerraPairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: "uusd".into()
+                            },
+                            amount: offer_amount,
+                        }
+                        .into(),
+                        belief_price: Some(Decimal::percent(6000u64)),
+                        max_spread: Some(Decimal::percent(1u64)),
+                        to: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("m_apple"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: String::from("mirror_mint"),
+                        amount: burn_amount,
+                        msg: to_binary(&MirrorMintCw20HookMsg::Burn {
+                            position_idx: Uint128::from(1u64),
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "check_short_position"),
+                attr("position_idx", "1"),
+                attr("burn_amount", burn_amount),
+            ]
+        );
+    }
+
+    #[test]
+    fn reduce_short_position_by_worker() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+
+        let burn_amount = Uint128::from(4000u64);
+        let msg = ExecuteMsg::ReduceShortPosition {
+            position_idx: Uint128::from(1u64),
+            burn_amount,
+            max_spread: None,
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Offer covers the simulated 160000 UST plus tax
+        let offer_amount = Uint128::from(161601u64);
+        assert_eq!(
+            deps.querier.deduct_tax(offer_amount).unwrap(),
+            Uint128::from(160000u64)
+        );
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("m_apple_pair"),
+                    funds: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: Uint128::from(160000u64),
+                    }],
+                    msg: to_binary(&TerraPairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: "uusd".into()
+                            },
+                            amount: Uint128::from(160000u64),
+                        }
+                        .into(),
+                        belief_price: Some(Decimal::percent(4000u64)),
+                        max_spread: Some(Decimal::percent(1u64)),
+                        to: None,
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("m_apple"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: String::from("mirror_mint"),
+                        amount: burn_amount,
+                        msg: to_binary(&MirrorMintCw20HookMsg::Burn {
+                            position_idx: Uint128::from(1u64),
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn reduce_short_position_fails_if_burn_amount_is_too_large() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let msg = ExecuteMsg::ReduceShortPosition {
+            position_idx: Uint128::from(1u64),
+            burn_amount: Uint128::from(10000u64),
+            max_spread: None,
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("Burn amount must be less than minted amount")
+        );
+    }
+
+    #[test]
+    fn withdraw_short_collateral_fails_if_below_min_collateral_ratio() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let msg = ExecuteMsg::WithdrawShortCollateral {
+            position_idx: Uint128::from(1u64),
+            collateral_ratio: Decimal::percent(140u64),
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("Collateral ratio must be greater than min collateral ratio")
+        );
+    }
+
+    #[test]
+    fn withdraw_short_collateral_by_worker() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let msg = ExecuteMsg::WithdrawShortCollateral {
+            position_idx: Uint128::from(1u64),
+            collateral_ratio: Decimal::percent(200u64),
+        };
+
+        let info = mock_info("worker", &[]);
+
+        // Collateral ratio is 250%, keep 200%
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let withdraw_amount = Uint128::from(200000u64);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mirror_mint"),
+                    funds: vec![],
+                    msg: to_binary(&MirrorMintExecuteMsg::Withdraw {
+                        position_idx: Uint128::from(1u64),
+                        collateral: Some(
+                            Asset {
+                                info: AssetInfo::Token {
+                                    contract_addr: String::from("aterra_contract"),
+                                },
+                                amount: withdraw_amount,
+                            }
+                            .into()
+                        ),
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::WithdrawShortCollateralHook {
+                        prev_aterra_balance: Uint128::from(1000000u64),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "withdraw_short_collateral"),
+                attr("position_idx", "1"),
+                attr("amount", withdraw_amount),
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_short_collateral_hook_by_itself() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        // Mirror mint returned 190000 aUST
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1190000u64))],
+            )],
+            &[],
+            &[],
+        );
+
+        let msg = ExecuteMsg::WithdrawShortCollateralHook {
+            prev_aterra_balance: Uint128::from(1000000u64),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("addr", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "withdraw_short_collateral_hook"),
+                attr("returned_amount", "190000"),
+            ]
+        );
+
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(state.aterra_collateral, Uint128::from(810000u64));
+    }
+
+    #[test]
+    fn open_delta_neutral_fails_if_not_enough_aterra() {
+        let mut deps = _instantiate_strategy();
+        deps.querier
+            .with_anchor_epoch_state(Decimal::one(), Uint128::from(1000000u64));
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1000000u64))],
+            )],
+            &[],
+            &[],
+        );
+
+        let msg = ExecuteMsg::OpenDeltaNeutral {
+            mirror_asset_addr: String::from("m_apple"),
+            amount: Uint128::from(500000u64),
+            collateral_ratio: Decimal::percent(250u64),
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, StdError::generic_err("Not enough aUST for collateral"));
+    }
+
+    #[test]
+    fn open_delta_neutral_by_worker() {
+        let mut deps = _instantiate_strategy();
+        deps.querier
+            .with_anchor_epoch_state(Decimal::percent(125u64), Uint128::from(1000000u64));
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1000000u64))],
+            )],
+            &[],
+            &[],
+        );
+
+        let mut state = read_state(deps.as_ref().storage).unwrap();
+        state.anchor_deposited = Uint128::from(1000000u64);
+        store_state(deps.as_mut().storage, &state).unwrap();
+
+        let msg = ExecuteMsg::OpenDeltaNeutral {
+            mirror_asset_addr: String::from("m_apple"),
+            amount: Uint128::from(200000u64),
+            collateral_ratio: Decimal::percent(250u64),
+        };
+
+        let info = mock_info("worker", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 500000 UST of collateral at the exchange rate
+        let aterra_amount = Uint128::from(400000u64);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("aterra_contract"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: String::from("mirror_mint"),
+                        amount: aterra_amount,
+                        msg: to_binary(&MirrorMintCw20HookMsg::OpenPosition {
+                            asset_info: TerraswapAssetInfo::Token {
+                                contract_addr: String::from("m_apple"),
+                            },
+                            collateral_ratio: Decimal::percent(250u64),
+                            short_params: None,
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::DepositMirrorHook {
+                        mirror_asset_addr: String::from("m_apple"),
+                    })
+                    .unwrap(),
+                })),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "open_delta_neutral"),
+                attr("position_idx", "1"),
+                attr("aterra_amount", aterra_amount),
+                attr("amount", "200000"),
+            ]
+        );
+
+        let state: State =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(state.aterra_collateral, aterra_amount);
+        // 40% of the aUST balance moved to the collateral
+        assert_eq!(state.anchor_deposited, Uint128::from(600000u64));
+    }
+
+    #[test]
+    fn close_delta_neutral_realizes_liquidity_before_burning() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
+                &[
+                    (&String::from("m_apple_pair"), &Uint128::from(500000u64)),
+                    (&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(100u64)),
+                ],
+            )],
+            &[(&String::from("m_liquidity_token"), &Uint128::from(100000u64))],
+            &[],
+        );
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000u64),
+            }],
+        )]);
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: Uint128::from(5000u64),
+            pending_reward: Uint128::zero(),
+            is_short: false,
+        }]);
+
+        let msg = ExecuteMsg::CloseDeltaNeutral {
+            position_idx: Uint128::from(1u64),
+            max_spread: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg).unwrap();
+
+        // 2000 LP holds the 10000 m_apple minted
+        assert_eq!(
+            res.messages[2],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::RealizeMirrorLpHook {
+                    mirror_asset_addr: String::from("m_apple"),
+                    lp_amount: Uint128::from(2000u64),
+                    prev_stable_balance: Uint128::from(1000u64),
+                    prev_asset_balance: Uint128::from(100u64),
+                })
+                .unwrap(),
+            }))
+        );
+        assert_eq!(
+            res.messages[3],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::CloseDeltaNeutralHook {
+                    position_idx: Uint128::from(1u64),
+                    max_spread: None,
+                })
+                .unwrap(),
+            }))
+        );
+    }
+
+    #[test]
+    fn close_delta_neutral_hook_burns_debt_and_withdraws_collateral() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        // Withdrawn liquidity covers 6000 of the 10000 minted
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("m_apple"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(6000u64))],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
+            &[],
+            &[(&String::from("m_apple"), &Decimal::percent(4000u64))],
+        );
+
+        let msg = ExecuteMsg::CloseDeltaNeutralHook {
+            position_idx: Uint128::from(1u64),
+            max_spread: None,
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let burn_msg = |amount: u64| {
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("m_apple"),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: String::from("mirror_mint"),
+                    amount: Uint128::from(amount),
+                    msg: to_binary(&MirrorMintCw20HookMsg::Burn {
+                        position_idx: Uint128::from(1u64),
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+            }))
+        };
+        assert_eq!(
+            res.messages,
+            vec![
+                burn_msg(6000),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("m_apple_pair"),
+                    funds: vec![Coin {
+                        denom: "uusd".into(),
+                        amount: Uint128::from(160000u64),
+                    }],
+                    msg: to_binary(&TerraPairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: "uusd".into()
+                            },
+                            amount: Uint128::from(160000u64),
+                        }
+                        .into(),
+                        belief_price: Some(Decimal::percent(4000u64)),
+                        max_spread: Some(Decimal::percent(1u64)),
+                        to: None,
+                    })
+                    .unwrap(),
+                })),
+                burn_msg(4000),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mirror_mint"),
+                    funds: vec![],
+                    msg: to_binary(&MirrorMintExecuteMsg::Withdraw {
+                        position_idx: Uint128::from(1u64),
+                        collateral: None,
                     })
                     .unwrap(),
                 })),
                 SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: String::from(MOCK_CONTRACT_ADDR),
                     funds: vec![],
-                    msg: to_binary(&ExecuteMsg::CompoundMirror {}).unwrap(),
+                    msg: to_binary(&ExecuteMsg::WithdrawShortCollateralHook {
+                        prev_aterra_balance: Uint128::zero(),
+                    })
+                    .unwrap(),
                 })),
             ]
         );
-        assert_eq!(res.attributes, vec![attr("action", "withdraw_all"),]);
+
+        let res: ShortPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ShortPositions {}).unwrap())
+                .unwrap();
+        assert_eq!(res.positions, vec![]);
     }
 
     #[test]
-    fn withdraw_all_both_anchor_and_mirror() {
-        let mut deps = _instantiate_strategy();
+    fn unlock_short_proceeds_fails_if_still_locked() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        let env = mock_env();
+        deps.querier.with_locked_proceeds(&[(
+            Uint128::from(1u64),
+            Uint128::from(400000u64),
+            env.block.time.seconds() + 1,
+        )]);
 
-        deps.querier.with_mirror_asset(&[
+        let msg = ExecuteMsg::UnlockShortProceeds {
+            position_idxs: vec![Uint128::from(1u64)],
+        };
+        let info = mock_info("keeper", &[]);
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(res, StdError::generic_err("No unlockable proceeds"));
+    }
+
+    #[test]
+    fn unlock_short_proceeds_fails_if_position_not_registered() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let msg = ExecuteMsg::UnlockShortProceeds {
+            position_idxs: vec![Uint128::from(2u64)],
+        };
+        let info = mock_info("keeper", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, StdError::generic_err("Short position not found"));
+    }
+
+    #[test]
+    fn unlock_short_proceeds_fails_if_lock_query_fails() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        deps.querier.with_mirror_lock_error("Generic error: out of gas");
+
+        let msg = ExecuteMsg::UnlockShortProceeds {
+            position_idxs: vec![Uint128::from(1u64)],
+        };
+        let info = mock_info("keeper", &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("Querier contract error: Generic error: out of gas")
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("Querier contract error: Generic error: out of gas")
+        );
+    }
+
+    #[test]
+    fn unlock_short_proceeds_by_keeper() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        let env = mock_env();
+        deps.querier.with_locked_proceeds(&[(
+            Uint128::from(1u64),
+            Uint128::from(400000u64),
+            env.block.time.seconds(),
+        )]);
+
+        let msg = ExecuteMsg::UnlockShortProceeds {
+            position_idxs: vec![Uint128::from(1u64)],
+        };
+        let info = mock_info("keeper", &[]);
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mirror_lock"),
+                msg: to_binary(&MirrorLockExecuteMsg::UnlockPositionFunds {
+                    positions_idx: vec![Uint128::from(1u64)],
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "unlock_short_proceeds"),
+                attr("position_idxs", "1"),
+                attr("unlock_amount", "400000"),
+            ]
+        );
+        assert!(
+            !read_short_position(deps.as_ref().storage, Uint128::from(1u64))
+                .unwrap()
+                .unwrap()
+                .proceeds_locked
+        );
+
+        // Mirror lock drops the lock info once the proceeds are unlocked
+        deps.querier.with_locked_proceeds(&[]);
+        query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap();
+    }
+
+    #[test]
+    fn total_balance_includes_locked_proceeds() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+
+        let before: Uint128 =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap())
+                .unwrap();
+
+        deps.querier.with_locked_proceeds(&[(
+            Uint128::from(1u64),
+            Uint128::from(400000u64),
+            mock_env().block.time.seconds(),
+        )]);
+        let after: Uint128 =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TotalBalance {}).unwrap())
+                .unwrap();
+        assert_eq!(after, before + Uint128::from(400000u64));
+    }
+
+    #[test]
+    fn query_balances() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(10000u64));
+        deps.querier.with_balance(&[
             (
-                &String::from("m_apple"),
-                &[
-                    String::from("m_apple_pair"),
-                    String::from("m_apple_liquidity_token"),
-                ],
+                &String::from(MOCK_CONTRACT_ADDR),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(300000u64),
+                }],
             ),
             (
-                &String::from("m_google"),
-                &[
-                    String::from("m_google_pair"),
-                    String::from("m_google_liquidity_token"),
-                ],
+                &String::from("m_apple_pair"),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000000000u64),
+                }],
             ),
-            (
-                &String::from("m_luna"),
+        ]);
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1000000u64))],
+                ),
+                (
+                    &String::from("m_apple"),
+                    &[(&String::from("m_apple_pair"), &Uint128::from(10000000u64))],
+                ),
+            ],
+            &[(&String::from("m_liquidity_token"), &Uint128::from(1000000000u64))],
+            &[(&String::from("m_apple"), &Decimal::percent(10000u64))],
+        );
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: Uint128::from(500000000u64),
+            pending_reward: Uint128::zero(),
+            is_short: false,
+        }]);
+        deps.querier.with_locked_proceeds(&[(
+            Uint128::from(1u64),
+            Uint128::from(400000u64),
+            mock_env().block.time.seconds(),
+        )]);
+
+        let res: BalancesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Balances {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            BalancesResponse {
+                idle_balance: Uint128::from(300000u64),
+                anchor_balance: Uint128::from(1000000u64),
+                // Nothing deposited through DepositAnchor, so all of it is charged 5% fee
+                anchor_balance_without_fee: Uint128::from(950000u64),
+                mirror_balances: vec![MirrorBalanceInfo {
+                    mirror_asset: String::from("m_apple"),
+                    lp_amount: Uint128::from(500000000u64),
+                    // 500 UST and 5 m_apple at the oracle price of 100 UST
+                    value: Uint128::from(1000000000u64),
+                }],
+                short_collateral: Uint128::from(1000000u64),
+                locked_proceeds: Uint128::from(400000u64),
+            }
+        );
+    }
+
+    #[test]
+    fn query_net_delta() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(4000u64));
+        deps.querier.with_mirror_asset(&[(
+            &String::from("m_apple"),
+            &[
+                String::from("m_apple_pair"),
+                String::from("m_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_reward_info(vec![MirrorStakingRewardInfoResponseItem {
+            asset_token: String::from("m_apple"),
+            bond_amount: Uint128::from(1000u64),
+            pending_reward: Uint128::zero(),
+            is_short: false,
+        }]);
+        deps.querier.with_token_info(
+            &[(
+                &String::from("m_apple"),
                 &[
-                    String::from("m_luna_pair"),
-                    String::from("m_luna_liquidity_token"),
+                    (&String::from("m_apple_pair"), &Uint128::from(500000u64)),
+                    (&String::from(MOCK_CONTRACT_ADDR), &Uint128::from(1000u64)),
                 ],
-            ),
-        ]);
+            )],
+            &[(&String::from("m_liquidity_token"), &Uint128::from(100000u64))],
+            &[],
+        );
 
-        deps.querier.with_reward_info(vec![
-            MirrorStakingRewardInfoResponseItem {
-                asset_token: String::from("m_apple"),
-                bond_amount: Uint128::from(100000000u64),
-                pending_reward: Uint128::from(100000u64),
-                is_short: false,
-            },
-            MirrorStakingRewardInfoResponseItem {
-                asset_token: String::from("m_google"),
-                bond_amount: Uint128::from(110000000u64),
-                pending_reward: Uint128::from(110000u64),
-                is_short: false,
-            },
-            MirrorStakingRewardInfoResponseItem {
-                asset_token: String::from("m_luna"),
-                bond_amount: Uint128::from(120000000u64),
-                pending_reward: Uint128::from(120000u64),
-                is_short: false,
-            },
-        ]);
+        let res: NetDeltaResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NetDelta {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res.assets,
+            vec![NetDeltaInfo {
+                mirror_asset: String::from("m_apple"),
+                long_amount: Uint128::from(6000u64),
+                short_amount: Uint128::from(10000u64),
+                net_long: Uint128::zero(),
+                net_short: Uint128::from(4000u64),
+            }]
+        );
+    }
 
-        let msg = ExecuteMsg::WithdrawAll {};
+    #[test]
+    fn short_position_queries_skip_positions_closed_in_mirror() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(10000u64));
 
-        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        // Mirror mint removes the position once it is liquidated
+        deps.querier
+            .with_mirror_positions(vec![], Decimal::percent(150u64));
 
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res: ShortPositionsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ShortPositions {}).unwrap())
+                .unwrap();
+        assert_eq!(res.positions, vec![]);
+
+        let res: NetDeltaResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NetDelta {}).unwrap())
+                .unwrap();
+        assert_eq!(res.assets, vec![]);
+    }
+
+    #[test]
+    fn remove_short_position() {
+        let mut deps = _instantiate_strategy_with_short_position(Decimal::percent(10000u64));
+        let msg = ExecuteMsg::RemoveShortPosition {
+            position_idx: Uint128::from(1u64),
+        };
 
+        let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("unauthorized"));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("Short position is still open"));
+
+        // Mirror lock has released the proceeds of the closed position
+        deps.querier
+            .with_mirror_positions(vec![], Decimal::percent(150u64));
+        deps.querier.with_locked_proceeds(&[]);
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg.clone())
+            .unwrap();
         assert_eq!(
-            res.messages,
+            res.attributes,
             vec![
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::WithdrawAnchor {
-                        aterra_amount: None
-                    })
-                    .unwrap(),
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
-                        mirror_lp_amount: Uint128::from(100000000u64),
-                        mirror_asset_addr: String::from("m_apple")
-                    })
-                    .unwrap(),
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
-                        mirror_lp_amount: Uint128::from(110000000u64),
-                        mirror_asset_addr: String::from("m_google")
-                    })
-                    .unwrap(),
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::WithdrawMirror {
-                        mirror_lp_amount: Uint128::from(120000000u64),
-                        mirror_asset_addr: String::from("m_luna")
-                    })
-                    .unwrap(),
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::CompoundMirror {}).unwrap(),
-                })),
+                attr("action", "remove_short_position"),
+                attr("position_idx", "1"),
             ]
         );
-        assert_eq!(res.attributes, vec![attr("action", "withdraw_all"),]);
+        assert_eq!(
+            read_short_position(deps.as_ref().storage, Uint128::from(1u64)).unwrap(),
+            None
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("governance", &[]), msg)
+            .unwrap_err();
+        assert_eq!(res, StdError::generic_err("Short position not found"));
     }
 
     // utils
+    fn _instantiate_strategy_with_short_position(
+        price: Decimal,
+    ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+        let mut deps = _instantiate_strategy();
+
+        let aterra_amount = Uint128::from(1000000u64);
+        let msg = ExecuteMsg::OpenShortPosition {
+            aterra_amount,
+            collateral_ratio: Decimal::percent(250u64),
+            mirror_asset_addr: String::from("m_apple"),
+            belief_price: None,
+            max_spread: None,
+        };
+        let info = mock_info("worker", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        deps.querier.with_mirror_positions(
+            vec![MirrorPositionResponse {
+                idx: Uint128::from(1u64),
+                owner: String::from(MOCK_CONTRACT_ADDR),
+                collateral: TerraswapAsset {
+                    info: TerraswapAssetInfo::Token {
+                        contract_addr: String::from("aterra_contract"),
+                    },
+                    amount: aterra_amount,
+                },
+                asset: TerraswapAsset {
+                    info: TerraswapAssetInfo::Token {
+                        contract_addr: String::from("m_apple"),
+                    },
+                    amount: Uint128::from(10000u64),
+                },
+                is_short: true,
+            }],
+            Decimal::percent(150u64),
+        );
+        // Sale proceeds are left out of the balances unless a test locks them
+        deps.querier.with_locked_proceeds(&[(
+            Uint128::from(1u64),
+            Uint128::zero(),
+            mock_env().block.time.seconds(),
+        )]);
+        deps.querier
+            .with_anchor_epoch_state(Decimal::one(), Uint128::from(1000000u64));
+        deps.querier.with_token_info(
+            &[(
+                &String::from("aterra_contract"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &aterra_amount)],
+            )],
+            &[],
+            &[(&String::from("m_apple"), &price)],
+        );
+
+        deps
+    }
+
     fn _instantiate_strategy() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
         let mut deps = mock_dependencies_with_querier(20, &[]);
 
@@ -1311,6 +3917,7 @@ mod tests {
             performance_fee: Decimal::percent(5u64),
             stable_denom: String::from("uusd"),
             anchor_market: String::from("anchor_market"),
+            anchor_token: String::from("anchor_token"),
             mirror_token: String::from("mirror_token"),
             mirror_staking: String::from("mirror_staking"),
             mirror_mint: String::from("mirror_mint"),
@@ -1336,4 +3943,56 @@ mod tests {
 
         let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     }
+
+    fn _set_harvest_bounty(
+        deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+        harvest_bounty: Uint128,
+    ) {
+        let msg = ExecuteMsg::UpdateConfig {
+            controller: None,
+            performance_fee: None,
+            insurance_fee: None,
+            anchor_allocation: None,
+            rebalance_band: None,
+            max_spread: None,
+            slippage_tolerance: None,
+            oracle_price_band: None,
+            short_ratio_low: None,
+            short_ratio_high: None,
+            keeper_bounty: None,
+            harvest_bounty: Some(harvest_bounty),
+            harvest_interval: None,
+        };
+
+        let info = mock_info("governance", &[]);
+
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    fn _with_mirror_reward(
+        deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+        mirror_balance: Uint128,
+    ) {
+        deps.querier.with_mirror_asset(&[(
+            &String::from("mirror_token"),
+            &[
+                String::from("ust_mir_pair"),
+                String::from("ust_mir_liquidity_token"),
+            ],
+        )]);
+        deps.querier.with_token_info(
+            &[
+                (
+                    &String::from("mirror_token"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &mirror_balance)],
+                ),
+                (
+                    &String::from("aterra_contract"),
+                    &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+                ),
+            ],
+            &[],
+            &[(&String::from("mirror_token"), &Decimal::percent(1000u64))],
+        );
+    }
 }
@@ -1,12 +1,15 @@
 #[cfg(test)]
 mod tests {
   use crate::contract::{execute, instantiate, query};
+  use crate::state::{read_state, store_state};
   use crate::testing::mock_querier::{mock_dependencies_with_querier, WasmMockQuerier};
 
   use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
   use cosmwasm_std::{
-    attr, from_binary, BankMsg, Coin, CosmosMsg, Decimal, OwnedDeps, StdError, SubMsg, Uint128,
+    attr, from_binary, to_binary, Coin, CosmosMsg, Decimal, OwnedDeps, StdError, SubMsg,
+    Uint128, WasmMsg,
   };
+  use athena::vault::ExecuteMsg as VaultExecuteMsg;
   use athena::vault_strategy::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
 
   #[test]
@@ -16,6 +19,17 @@ mod tests {
     let msg = ExecuteMsg::UpdateConfig {
       controller: Some(String::from("controller2")),
       performance_fee: Some(Decimal::percent(10u64)),
+      insurance_fee: None,
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: None,
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: None,
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: None,
+      harvest_interval: None,
     };
 
     let info = mock_info("addr", &[]);
@@ -31,6 +45,17 @@ mod tests {
     let msg = ExecuteMsg::UpdateConfig {
       controller: Some(String::from("controller2")),
       performance_fee: Some(Decimal::percent(101u64)),
+      insurance_fee: None,
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: None,
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: None,
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: None,
+      harvest_interval: None,
     };
     let info = mock_info("governance", &[]);
 
@@ -41,6 +66,90 @@ mod tests {
     );
   }
 
+  #[test]
+  fn update_config_fails_if_insurance_fee_is_greater_than_100() {
+    let mut deps = _instantiate_strategy();
+
+    let msg = ExecuteMsg::UpdateConfig {
+      controller: None,
+      performance_fee: None,
+      insurance_fee: Some(Decimal::percent(101u64)),
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: None,
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: None,
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: None,
+      harvest_interval: None,
+    };
+    let info = mock_info("governance", &[]);
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+      res,
+      StdError::generic_err("Insurance fee percentage must be less than 100%")
+    );
+  }
+
+  #[test]
+  fn update_config_fails_if_max_spread_is_greater_than_100() {
+    let mut deps = _instantiate_strategy();
+
+    let msg = ExecuteMsg::UpdateConfig {
+      controller: None,
+      performance_fee: None,
+      insurance_fee: None,
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: Some(Decimal::percent(101u64)),
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: None,
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: None,
+      harvest_interval: None,
+    };
+    let info = mock_info("governance", &[]);
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+      res,
+      StdError::generic_err("Max spread must be less than 100%")
+    );
+  }
+
+  #[test]
+  fn update_config_fails_if_short_ratio_band_is_invalid() {
+    let mut deps = _instantiate_strategy();
+
+    let msg = ExecuteMsg::UpdateConfig {
+      controller: None,
+      performance_fee: None,
+      insurance_fee: None,
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: None,
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: Some(Decimal::percent(300u64)),
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: None,
+      harvest_interval: None,
+    };
+    let info = mock_info("governance", &[]);
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+      res,
+      StdError::generic_err("Short ratio low must be less than short ratio high")
+    );
+  }
+
   #[test]
   fn update_config() {
     let mut deps = _instantiate_strategy();
@@ -48,6 +157,17 @@ mod tests {
     let msg = ExecuteMsg::UpdateConfig {
       controller: Some(String::from("controller2")),
       performance_fee: Some(Decimal::percent(10u64)),
+      insurance_fee: Some(Decimal::percent(20u64)),
+      anchor_allocation: None,
+      rebalance_band: None,
+      max_spread: None,
+      slippage_tolerance: None,
+      oracle_price_band: None,
+      short_ratio_low: None,
+      short_ratio_high: None,
+      keeper_bounty: None,
+      harvest_bounty: Some(Uint128::from(1000u64)),
+      harvest_interval: Some(3600u64),
     };
 
     let info = mock_info("governance", &[]);
@@ -63,12 +183,25 @@ mod tests {
         controller: String::from("controller2"),
         vault: String::from("vault"),
         performance_fee: Decimal::percent(10u64),
+        insurance_fee: Decimal::percent(20u64),
+        anchor_allocation: Decimal::zero(),
+        rebalance_band: Decimal::percent(5u64),
+        max_spread: Decimal::percent(1u64),
+        slippage_tolerance: Decimal::percent(1u64),
+        oracle_price_band: Decimal::percent(5u64),
+        short_ratio_low: Decimal::percent(200u64),
+        short_ratio_high: Decimal::percent(300u64),
+        keeper_bounty: Uint128::zero(),
+        harvest_bounty: Uint128::from(1000u64),
+        harvest_interval: 3600u64,
         stable_denom: String::from("uusd"),
         anchor_market: String::from("anchor_market"),
         aterra_contract: String::from("aterra_contract"),
+        anchor_token: String::from("anchor_token"),
         mirror_token: String::from("mirror_token"),
         mirror_staking: String::from("mirror_staking"),
         mirror_mint: String::from("mirror_mint"),
+        mirror_lock: String::from("mirror_lock"),
         mirror_oracle: String::from("mirror_oracle"),
         terraswap_factory: String::from("terraswap_factory"),
       },
@@ -177,19 +310,26 @@ mod tests {
     let amount_without_tax = deps.querier.deduct_tax(withdraw_amount).unwrap();
     assert_eq!(
       res.messages,
-      vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-        to_address: String::from("vault"),
-        amount: vec![Coin {
+      vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("vault"),
+        msg: to_binary(&VaultExecuteMsg::Report {
+          gain: Uint128::zero(),
+          loss: Uint128::zero(),
+          debt_payment: amount_without_tax,
+        })
+        .unwrap(),
+        funds: vec![Coin {
           denom: "uusd".to_string(),
           amount: amount_without_tax,
         }],
-      }),)]
+      }))]
     );
     assert_eq!(
       res.attributes,
       vec![
         attr("action", "withdraw_to_vault"),
         attr("amount", withdraw_amount),
+        attr("loss", "0"),
       ]
     );
   }
@@ -210,19 +350,26 @@ mod tests {
     let amount_without_tax = deps.querier.deduct_tax(withdraw_amount).unwrap();
     assert_eq!(
       res.messages,
-      vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-        to_address: String::from("vault"),
-        amount: vec![Coin {
+      vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("vault"),
+        msg: to_binary(&VaultExecuteMsg::Report {
+          gain: Uint128::zero(),
+          loss: Uint128::zero(),
+          debt_payment: amount_without_tax,
+        })
+        .unwrap(),
+        funds: vec![Coin {
           denom: "uusd".to_string(),
           amount: amount_without_tax,
         }],
-      }),)]
+      }))]
     );
     assert_eq!(
       res.attributes,
       vec![
         attr("action", "withdraw_to_vault"),
         attr("amount", withdraw_amount),
+        attr("loss", "0"),
       ]
     );
   }
@@ -248,23 +395,154 @@ mod tests {
     let amount_without_tax = deps.querier.deduct_tax(strategy_balance).unwrap();
     assert_eq!(
       res.messages,
-      vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-        to_address: String::from("vault"),
-        amount: vec![Coin {
+      vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("vault"),
+        msg: to_binary(&VaultExecuteMsg::Report {
+          gain: Uint128::zero(),
+          loss: Uint128::zero(),
+          debt_payment: amount_without_tax,
+        })
+        .unwrap(),
+        funds: vec![Coin {
+          denom: "uusd".to_string(),
+          amount: amount_without_tax,
+        }],
+      }))]
+    );
+    assert_eq!(
+      res.attributes,
+      vec![
+        attr("action", "withdraw_to_vault"),
+        attr("amount", strategy_balance),
+        attr("loss", "0"),
+      ]
+    );
+  }
+
+  #[test]
+  fn withdraw_to_vault_reports_loss_and_resets_high_water_mark() {
+    let mut deps = _instantiate_strategy();
+
+    let mut state = read_state(&deps.storage).unwrap();
+    state.high_water_mark = Uint128::from(95000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let strategy_balance = Uint128::from(120000000u64);
+    deps.querier.with_balance(&[(
+      &String::from(MOCK_CONTRACT_ADDR),
+      &[Coin {
+        denom: "uusd".to_string(),
+        amount: strategy_balance,
+      }],
+    )]);
+    deps.querier.with_token_info(
+      &[(
+        &String::from("aterra_contract"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+      )],
+      &[],
+      &[],
+    );
+    deps.querier
+      .with_anchor_epoch_state(Decimal::one(), Uint128::zero());
+    deps.querier.with_strategy_debt(Uint128::from(200000000u64));
+
+    let info = mock_info("worker", &[]);
+    let msg = ExecuteMsg::WithdrawToVault { amount: None };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // debt above the balance left after tax is reported as loss
+    let amount_without_tax = deps.querier.deduct_tax(strategy_balance).unwrap();
+    let loss = Uint128::from(200000000u64) - amount_without_tax;
+    assert_eq!(
+      res.messages,
+      vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("vault"),
+        msg: to_binary(&VaultExecuteMsg::Report {
+          gain: Uint128::zero(),
+          loss,
+          debt_payment: amount_without_tax,
+        })
+        .unwrap(),
+        funds: vec![Coin {
           denom: "uusd".to_string(),
           amount: amount_without_tax,
         }],
-      }),)]
+      }))]
     );
     assert_eq!(
       res.attributes,
       vec![
         attr("action", "withdraw_to_vault"),
         attr("amount", strategy_balance),
+        attr("loss", loss),
+      ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.high_water_mark, Uint128::zero());
+    assert_eq!(state.vault_debt, Uint128::zero());
+  }
+
+  #[test]
+  fn withdraw_to_vault_reports_debt_payment_if_unregistered() {
+    let mut deps = _instantiate_strategy();
+    deps.querier.with_strategy_unregistered();
+
+    let withdraw_amount = Uint128::from(100000000u64);
+    let msg = ExecuteMsg::WithdrawToVault {
+      amount: Some(withdraw_amount),
+    };
+
+    let info = mock_info("vault", &[]);
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let amount_without_tax = deps.querier.deduct_tax(withdraw_amount).unwrap();
+    assert_eq!(
+      res.messages,
+      vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("vault"),
+        msg: to_binary(&VaultExecuteMsg::Report {
+          gain: Uint128::zero(),
+          loss: Uint128::zero(),
+          debt_payment: amount_without_tax,
+        })
+        .unwrap(),
+        funds: vec![Coin {
+          denom: "uusd".to_string(),
+          amount: amount_without_tax,
+        }],
+      }))]
+    );
+    assert_eq!(
+      res.attributes,
+      vec![
+        attr("action", "withdraw_to_vault"),
+        attr("amount", withdraw_amount),
+        attr("loss", Uint128::zero()),
       ]
     );
   }
 
+  #[test]
+  fn withdraw_to_vault_propagates_vault_query_error() {
+    let mut deps = _instantiate_strategy();
+    deps.querier.with_strategy_debt_error("Querier system error");
+
+    let msg = ExecuteMsg::WithdrawToVault {
+      amount: Some(Uint128::from(100000000u64)),
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("vault", &[]), msg).unwrap_err();
+
+    assert_eq!(
+      res,
+      StdError::generic_err("Querier contract error: Querier system error")
+    );
+  }
+
   // utils
   fn _instantiate_strategy() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
     let mut deps = mock_dependencies_with_querier(20, &[]);
@@ -275,6 +553,7 @@ mod tests {
       performance_fee: Decimal::percent(5u64),
       stable_denom: String::from("uusd"),
       anchor_market: String::from("anchor_market"),
+      anchor_token: String::from("anchor_token"),
       mirror_token: String::from("mirror_token"),
       mirror_staking: String::from("mirror_staking"),
       mirror_mint: String::from("mirror_mint"),
@@ -8,10 +8,20 @@ use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 
 use anchor_market::market::{
+    BorrowerInfoResponse as AnchorMarketBorrowerInfoResponse,
     ConfigResponse as AnchorMarketConfigResponse,
     EpochStateResponse as AnchorMarketEpochStateResponse, QueryMsg as AnchorMarketQueryMsg,
 };
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use mirror_protocol::lock::{
+    PositionLockInfoResponse as MirrorPositionLockInfoResponse, QueryMsg as MirrorLockQueryMsg,
+};
+use mirror_protocol::mint::{
+    AssetConfigResponse as MirrorAssetConfigResponse, ConfigResponse as MirrorMintConfigResponse,
+    NextPositionIdxResponse as MirrorNextPositionIdxResponse,
+    PositionResponse as MirrorPositionResponse, PositionsResponse as MirrorPositionsResponse,
+    QueryMsg as MirrorMintQueryMsg,
+};
 use mirror_protocol::oracle::{
     PriceResponse as MirrorOraclePriceResponse, QueryMsg as MirrorOracleQueryMsg,
 };
@@ -22,12 +32,17 @@ use mirror_protocol::staking::{
 use athena::controller::{
     ConfigResponse as ControllerConfigResponse, QueryMsg as ControllerQueryMsg, UserRole,
 };
+use athena::vault::{
+    QueryMsg as VaultQueryMsg, StrategiesResponse, StrategyDebtResponse, StrategyInfo,
+};
 use athena::vault_strategy::QueryMsg;
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 use terraswap::asset::AssetInfo;
 use terraswap::asset::PairInfo;
 use terraswap::factory::QueryMsg as TerraSwapFactoryQueryMsg;
-use terraswap::pair::{QueryMsg as TerraSwapQueryMsg, SimulationResponse};
+use terraswap::pair::{
+    QueryMsg as TerraSwapQueryMsg, ReverseSimulationResponse, SimulationResponse,
+};
 
 static DECIMAL_FRACTIONAL: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
@@ -40,7 +55,14 @@ pub struct WasmMockQuerier {
     tax: (Decimal, Uint128),
     exchange_rate: Decimal,
     aterra_supply: Uint128,
+    anchor_pending_rewards: Decimal,
     reward_info: Vec<MirrorStakingRewardInfoResponseItem>,
+    strategy_debt: Option<Uint128>,
+    strategy_debt_error: Option<String>,
+    mirror_positions: Vec<MirrorPositionResponse>,
+    min_collateral_ratio: Decimal,
+    locked_proceeds: HashMap<u128, (Uint128, u64)>,
+    mirror_lock_error: Option<String>,
 }
 
 #[derive(Clone, Default)]
@@ -174,6 +196,133 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if let Ok(VaultQueryMsg::Strategies {}) = from_binary(msg) {
+                    let strategies = match self.strategy_debt {
+                        Some(_) => vec![StrategyInfo {
+                            addr: MOCK_CONTRACT_ADDR.to_string(),
+                            weight: Decimal::one(),
+                            debt_limit: Uint128::MAX,
+                        }],
+                        None => vec![],
+                    };
+                    return SystemResult::Ok(ContractResult::from(to_binary(
+                        &StrategiesResponse { strategies },
+                    )));
+                }
+                if let Ok(VaultQueryMsg::StrategyDebt { strategy }) = from_binary(msg) {
+                    if let Some(error) = &self.strategy_debt_error {
+                        return SystemResult::Ok(ContractResult::Err(error.to_string()));
+                    }
+                    let total_debt = match self.strategy_debt {
+                        Some(total_debt) => total_debt,
+                        None => {
+                            return SystemResult::Ok(ContractResult::Err(
+                                "Strategy not found".to_string(),
+                            ))
+                        }
+                    };
+                    return SystemResult::Ok(ContractResult::from(to_binary(
+                        &StrategyDebtResponse {
+                            strategy,
+                            total_debt,
+                            total_gain: Uint128::zero(),
+                            total_loss: Uint128::zero(),
+                            last_report: 0u64,
+                            debt_limit: Uint128::zero(),
+                        },
+                    )));
+                }
+                if contract_addr == "mirror_lock" {
+                    if let Some(error) = &self.mirror_lock_error {
+                        return SystemResult::Ok(ContractResult::Err(error.to_string()));
+                    }
+                    return match from_binary(msg).unwrap() {
+                        MirrorLockQueryMsg::PositionLockInfo { position_idx } => {
+                            match self.locked_proceeds.get(&position_idx.u128()) {
+                                Some((locked_amount, unlock_time)) => {
+                                    SystemResult::Ok(ContractResult::from(to_binary(
+                                        &MirrorPositionLockInfoResponse {
+                                            idx: position_idx,
+                                            receiver: MOCK_CONTRACT_ADDR.to_string(),
+                                            locked_amount: *locked_amount,
+                                            unlock_time: *unlock_time,
+                                        },
+                                    )))
+                                }
+                                None => SystemResult::Ok(ContractResult::Err(
+                                    "Generic error: There are no locked funds for this position idx"
+                                        .to_string(),
+                                )),
+                            }
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    };
+                }
+                if contract_addr == "mirror_mint" {
+                    return match from_binary(msg).unwrap() {
+                        MirrorMintQueryMsg::Config {} => {
+                            SystemResult::Ok(ContractResult::from(to_binary(
+                                &MirrorMintConfigResponse {
+                                    owner: String::from("owner"),
+                                    oracle: String::from("mirror_oracle"),
+                                    collector: String::from("collector"),
+                                    collateral_oracle: String::from("collateral_oracle"),
+                                    staking: String::from("mirror_staking"),
+                                    terraswap_factory: String::from("terraswap_factory"),
+                                    lock: String::from("mirror_lock"),
+                                    base_denom: "uusd".to_string(),
+                                    token_code_id: 1u64,
+                                    protocol_fee_rate: Decimal::percent(1u64),
+                                },
+                            )))
+                        }
+                        MirrorMintQueryMsg::NextPositionIdx {} => {
+                            SystemResult::Ok(ContractResult::from(to_binary(
+                                &MirrorNextPositionIdxResponse {
+                                    next_position_idx: Uint128::from(
+                                        self.mirror_positions.len() as u128 + 1,
+                                    ),
+                                },
+                            )))
+                        }
+                        MirrorMintQueryMsg::Position { position_idx } => {
+                            match self.mirror_positions.iter().find(|p| p.idx == position_idx) {
+                                Some(position) => {
+                                    SystemResult::Ok(ContractResult::from(to_binary(position)))
+                                }
+                                None => SystemResult::Ok(ContractResult::Err(
+                                    "Position not found".to_string(),
+                                )),
+                            }
+                        }
+                        MirrorMintQueryMsg::AssetConfig { asset_token } => {
+                            SystemResult::Ok(ContractResult::from(to_binary(
+                                &MirrorAssetConfigResponse {
+                                    token: asset_token,
+                                    auction_discount: Decimal::percent(20u64),
+                                    min_collateral_ratio: self.min_collateral_ratio,
+                                    end_price: None,
+                                    ipo_params: None,
+                                },
+                            )))
+                        }
+                        MirrorMintQueryMsg::Positions {
+                            start_after, limit, ..
+                        } => {
+                            let mut positions: Vec<MirrorPositionResponse> = self
+                                .mirror_positions
+                                .iter()
+                                .filter(|p| Some(p.idx) > start_after)
+                                .cloned()
+                                .collect();
+                            positions.sort_by_key(|p| p.idx);
+                            positions.truncate(limit.unwrap_or(10u32) as usize);
+                            SystemResult::Ok(ContractResult::from(to_binary(
+                                &MirrorPositionsResponse { positions },
+                            )))
+                        }
+                    };
+                }
                 match from_binary(&msg) {
                     Ok(QueryMsg::Config {}) => {
                         if contract_addr.clone() == String::from("anchor_market") {
@@ -250,6 +399,17 @@ impl WasmMockQuerier {
                                         },
                                     )))
                                 }
+                                Ok(AnchorMarketQueryMsg::BorrowerInfo { borrower, .. }) => {
+                                    SystemResult::Ok(ContractResult::from(to_binary(
+                                        &AnchorMarketBorrowerInfoResponse {
+                                            borrower,
+                                            interest_index: Decimal::one().into(),
+                                            reward_index: Decimal::zero().into(),
+                                            loan_amount: Uint128::zero().into(),
+                                            pending_rewards: self.anchor_pending_rewards.into(),
+                                        },
+                                    )))
+                                }
                                 _ => match from_binary(&msg) {
                                     Ok(TerraSwapQueryMsg::Simulation { offer_asset }) => {
                                         let mut mirror_token: String = String::default();
@@ -270,6 +430,22 @@ impl WasmMockQuerier {
                                             },
                                         )))
                                     }
+                                    Ok(TerraSwapQueryMsg::ReverseSimulation { ask_asset }) => {
+                                        let mut mirror_token: String = String::default();
+                                        if let AssetInfo::Token { contract_addr } = &ask_asset.info {
+                                            mirror_token = contract_addr.clone()
+                                        }
+
+                                        let rate =
+                                            self.token_querier.prices.get(&mirror_token).unwrap();
+                                        SystemResult::Ok(ContractResult::from(to_binary(
+                                            &ReverseSimulationResponse {
+                                                offer_amount: ask_asset.amount * *rate,
+                                                spread_amount: Uint128::zero(),
+                                                commission_amount: Uint128::zero(),
+                                            },
+                                        )))
+                                    }
                                     _ => match from_binary(&msg) {
                                         Ok(MirrorStakingQueryMsg::RewardInfo { .. }) => {
                                             SystemResult::Ok(ContractResult::from(to_binary(
@@ -456,7 +632,14 @@ impl WasmMockQuerier {
             tax: (Decimal::percent(1), Uint128::from(1000000u128)),
             exchange_rate: Decimal::zero(),
             aterra_supply: Uint128::zero(),
+            anchor_pending_rewards: Decimal::zero(),
             reward_info: vec![],
+            strategy_debt: Some(Uint128::zero()),
+            strategy_debt_error: None,
+            mirror_positions: vec![],
+            min_collateral_ratio: Decimal::percent(150u64),
+            locked_proceeds: HashMap::new(),
+            mirror_lock_error: None,
         }
     }
 
@@ -480,6 +663,10 @@ impl WasmMockQuerier {
         self.aterra_supply = aterra_supply;
     }
 
+    pub fn with_anchor_pending_rewards(&mut self, pending_rewards: Decimal) {
+        self.anchor_pending_rewards = pending_rewards;
+    }
+
     pub fn with_mirror_asset(&mut self, pair_info: &[(&String, &[String; 2])]) {
         self.pair_info_querier = PairInfoQuerier::new(pair_info);
     }
@@ -505,4 +692,40 @@ impl WasmMockQuerier {
     pub fn with_reward_info(&mut self, reward_info: Vec<MirrorStakingRewardInfoResponseItem>) {
         self.reward_info = reward_info;
     }
+
+    pub fn with_strategy_debt(&mut self, strategy_debt: Uint128) {
+        self.strategy_debt = Some(strategy_debt);
+    }
+
+    pub fn with_strategy_unregistered(&mut self) {
+        self.strategy_debt = None;
+    }
+
+    pub fn with_strategy_debt_error(&mut self, error: &str) {
+        self.strategy_debt_error = Some(error.to_string());
+    }
+
+    pub fn with_mirror_positions(
+        &mut self,
+        mirror_positions: Vec<MirrorPositionResponse>,
+        min_collateral_ratio: Decimal,
+    ) {
+        self.mirror_positions = mirror_positions;
+        self.min_collateral_ratio = min_collateral_ratio;
+    }
+
+    /// Set locked amount and unlock time by position idx
+    pub fn with_locked_proceeds(&mut self, locked_proceeds: &[(Uint128, Uint128, u64)]) {
+        self.locked_proceeds = locked_proceeds
+            .iter()
+            .map(|(position_idx, locked_amount, unlock_time)| {
+                (position_idx.u128(), (*locked_amount, *unlock_time))
+            })
+            .collect();
+    }
+
+    /// Fail mirror lock queries with the error
+    pub fn with_mirror_lock_error(&mut self, error: &str) {
+        self.mirror_lock_error = Some(error.to_string());
+    }
 }
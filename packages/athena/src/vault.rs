@@ -17,17 +17,33 @@ pub struct InstantiateMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     /// Update configuration
     UpdateConfig {
         controller: Option<String>,
-        strategy: Option<String>,
         invest_percentage: Option<Decimal>,
         lock_period: Option<u64>,
         force_withdraw: Option<bool>,
+        early_withdraw: Option<bool>,
+        early_withdraw_penalty: Option<Decimal>,
+        penalty_recipient: Option<FeeRecipient>,
+        lock_tiers: Option<Vec<LockTier>>,
+        auto_invest: Option<bool>,
+        auto_invest_threshold: Option<Uint128>,
+        migration_tolerance: Option<Decimal>,
+        profit_unlock_period: Option<u64>,
+        management_fee: Option<Decimal>,
+        deposit_fee: Option<Decimal>,
+        withdraw_fee: Option<Decimal>,
+        fee_recipient: Option<FeeRecipient>,
     },
+    /// Replace strategy list
+    /// Strategies holding funds can not be removed
+    UpdateStrategies { strategies: Vec<StrategyInfo> },
     /// Deposit asset
-    Deposit {},
+    /// If lock_period is None, the default lock period is used without boost
+    Deposit { lock_period: Option<u64> },
     /// Withdraw asset
     Withdraw {
         withdraw_amount: Uint128,
@@ -37,13 +53,44 @@ pub enum ExecuteMsg {
     ClaimYield {},
     /// Claim principal
     ClaimPrincipal {},
-    /// Invest underlying to strategy
+    /// Invest underlying to strategies by weight
     Invest {},
+    /// Add sent underlying to the insurance reserve
+    FundInsurance {},
+    /// Declare a realized loss of the strategy
+    /// The insurance reserve covers the loss first, the rest writes down the principal
+    /// The loss is capped at the shortfall of the pool balance against the principal
+    DeclareLoss { amount: Uint128 },
+    /// Withdraw all funds from strategy and replace it with new strategy
+    MigrateStrategy {
+        strategy: String,
+        new_strategy: String,
+    },
+    MigrateStrategyHook {
+        strategy: String,
+        new_strategy: String,
+        expected_amount: Uint128,
+        previous_balance: Uint128,
+    },
+    /// Report harvest result of strategy
+    /// Sent underlying must match the debt payment
+    /// Retired strategy report only records the debt payment as returned
+    Report {
+        gain: Uint128,
+        loss: Uint128,
+        debt_payment: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Last deposit migrated by the previous migration,
+    /// continues the migration of single strategy vault deposits
+    pub start_after: Option<String>,
+    /// Maximum number of deposits migrated
+    pub limit: Option<u32>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -54,12 +101,57 @@ pub enum QueryMsg {
     DepositInfo { addr: String },
     /// Query current underlying balance in vault
     VaultBalance {},
-    /// Query current underlying balance in vault and strategy
+    /// Query current underlying balance in vault and strategies,
+    /// net of the performance fee accrued by strategies
     TotalBalance {},
     /// Query current underlying balance in vault
     Available {},
     /// Query current state of vault
     State {},
+    /// Query penalty applied if the deposit is withdrawn now
+    EarlyWithdrawPenalty { addr: String },
+    /// Query lock tiers available at deposit
+    LockTiers {},
+    /// Query strategies replaced by migration
+    RetiredStrategies {},
+    /// Query strategies with weight and debt limit
+    Strategies {},
+    /// Query balance lent to strategy, read from storage only
+    /// as strategies query it to compute their own balance
+    StrategyDebt { strategy: String },
+    /// Query balance of strategy and amount it would receive by Invest
+    StrategyCredit { strategy: String },
+    /// Query management fee accrued to treasury
+    Fees {},
+    /// Query deposit fee and share received by depositing the amount now
+    PreviewDeposit { amount: Uint128 },
+    /// Query fee, penalty and amount returned by withdrawing the amount now
+    PreviewWithdraw { addr: String, amount: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyInfo {
+    pub addr: String,
+    /// Percentage of invested balance targeted to the strategy
+    pub weight: Decimal,
+    /// Maximum balance of the strategy
+    pub debt_limit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockTier {
+    pub lock_period: u64,
+    /// Yield share weight of deposits locked for this period
+    pub boost: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRecipient {
+    /// Leave the fee in the vault for the remaining depositors
+    Depositors,
+    /// Send the fee to the treasury
+    Treasury,
 }
 
 // We define a custom struct for each query response
@@ -67,11 +159,21 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub contract_addr: String,
     pub controller: String,
-    pub strategy: Option<String>,
     pub stable_denom: String,
     pub invest_percentage: Decimal,
     pub lock_period: u64,
     pub force_withdraw: bool,
+    pub early_withdraw: bool,
+    pub early_withdraw_penalty: Decimal,
+    pub penalty_recipient: FeeRecipient,
+    pub auto_invest: bool,
+    pub auto_invest_threshold: Uint128,
+    pub migration_tolerance: Decimal,
+    pub profit_unlock_period: u64,
+    pub management_fee: Decimal,
+    pub deposit_fee: Decimal,
+    pub withdraw_fee: Decimal,
+    pub fee_recipient: FeeRecipient,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -79,14 +181,110 @@ pub struct DepositInfoResponse {
     pub principal: Uint128,
     pub current_amount: Uint128,
     pub share: Uint128,
+    pub boost: Decimal,
+    pub lock_period: u64,
     pub maturity: u64,
     pub yield_amount: Uint128,
     pub yield_claimed: Uint128,
     pub principal_claimed: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EarlyWithdrawPenaltyResponse {
+    pub maturity: u64,
+    pub penalty_rate: Decimal,
+    pub penalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockTiersResponse {
+    pub lock_tiers: Vec<LockTier>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategiesResponse {
+    pub strategies: Vec<StrategyInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyDebtResponse {
+    pub strategy: String,
+    /// Amount lent to the strategy and not paid back
+    pub total_debt: Uint128,
+    pub total_gain: Uint128,
+    pub total_loss: Uint128,
+    pub last_report: u64,
+    pub debt_limit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyCreditResponse {
+    pub strategy: String,
+    /// Current total balance of the strategy
+    pub balance: Uint128,
+    /// Amount the strategy would receive by Invest
+    pub credit_available: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeesResponse {
+    /// Annual management fee rate on the share pool
+    pub management_fee: Decimal,
+    /// Accrued management fee not paid to treasury yet
+    pub management_fee_payable: Uint128,
+    pub management_fee_paid: Uint128,
+    pub last_fee_accrual: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PreviewDepositResponse {
+    pub deposit_fee: Uint128,
+    /// Principal credited after the deposit fee
+    pub principal: Uint128,
+    pub share: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PreviewWithdrawResponse {
+    pub withdraw_fee: Uint128,
+    pub penalty: Uint128,
+    /// Amount sent to the depositor before tax
+    pub return_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RetiredStrategyResponse {
+    pub strategy: String,
+    pub retired_at: u64,
+    /// Total balance of the strategy before migration
+    pub expected_amount: Uint128,
+    /// Amount returned to the vault by migration
+    pub returned_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RetiredStrategiesResponse {
+    pub strategies: Vec<RetiredStrategyResponse>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub total_share: Uint128,
+    pub total_boosted_share: Uint128,
+    /// Unclaimed principal of all deposits
+    pub total_principal: Uint128,
+    /// Unclaimed principal of all deposits weighted by their lock boost
+    pub total_boosted_principal: Uint128,
     pub total_subsidized: Uint128,
+    /// Underlying kept aside to cover losses, not part of the share pool
+    pub insurance_reserve: Uint128,
+    /// Ratio of principal left after declared losses
+    pub principal_index: Decimal,
+    /// Reported gain not released to shares yet
+    pub locked_profit: Uint128,
+    pub last_report: u64,
+    /// Accrued management fee kept in the vault until paid to treasury
+    pub management_fee_payable: Uint128,
+    pub management_fee_paid: Uint128,
+    pub last_fee_accrual: u64,
 }
@@ -16,6 +16,8 @@ pub struct InstantiateMsg {
 
     /// Anchor market address to stake UST
     pub anchor_market: String,
+    /// Anchor token contract address
+    pub anchor_token: String,
     /// Mirror token contract address
     pub mirror_token: String,
     /// Mirror staking contract address
@@ -30,12 +32,44 @@ pub struct InstantiateMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     /// Update configuration
     UpdateConfig {
         controller: Option<String>,
         performance_fee: Option<Decimal>,
+        /// Percentage of performance fee sent to the vault insurance reserve
+        insurance_fee: Option<Decimal>,
+        /// Target percentage of total balance deposited to anchor by Deploy
+        anchor_allocation: Option<Decimal>,
+        /// Allowed drift of anchor balance from the anchor allocation before Rebalance acts
+        rebalance_band: Option<Decimal>,
+        /// Default max spread of terraswap swaps
+        max_spread: Option<Decimal>,
+        /// Slippage tolerance of mirror auto stake
+        slippage_tolerance: Option<Decimal>,
+        /// Allowed deviation of pool price from oracle price when providing liquidity
+        oracle_price_band: Option<Decimal>,
+        /// Target collateral ratio band of short positions kept by CheckShortPosition
+        short_ratio_low: Option<Decimal>,
+        short_ratio_high: Option<Decimal>,
+        /// UST paid to the keeper restoring a short position
+        keeper_bounty: Option<Uint128>,
+        /// UST paid to the caller of Harvest
+        harvest_bounty: Option<Uint128>,
+        /// Minimum seconds between harvests
+        harvest_interval: Option<u64>,
+    },
+    /// Only governance can execute
+    /// Unregister a short position closed or liquidated in mirror mint
+    RemoveShortPosition {
+        position_idx: Uint128,
     },
+    /// Deposit idle UST to anchor protocol up to the anchor allocation
+    Deploy {},
+    /// Deposit to or withdraw from anchor protocol
+    /// if anchor balance drifts out of the band around the anchor allocation
+    Rebalance {},
     /// Deposit UST to anchor protocol
     DepositAnchor {
         amount: Uint128,
@@ -45,24 +79,47 @@ pub enum ExecuteMsg {
         aterra_amount: Option<Uint128>,
     },
     /// Deposit UST to mirror staking
+    /// Swaps use the mirror oracle price as belief price,
+    /// `max_spread` overrides the default max spread
     DepositMirror {
         amount: Uint128,
         mirror_asset_addr: String,
+        max_spread: Option<Decimal>,
     },
     DepositMirrorHook {
         mirror_asset_addr: String,
     },
+    /// Only contract itself can execute
+    /// Record the LP bonded since `prev_lp_amount` to the mirror position
+    RecordMirrorLpHook {
+        mirror_asset_addr: String,
+        prev_lp_amount: Uint128,
+    },
     /// Withdraw UST from mirror staking
     WithdrawMirror {
         mirror_lp_amount: Uint128,
         mirror_asset_addr: String,
+        max_spread: Option<Decimal>,
     },
     WithdrawMirrorHook {
         mirror_asset_addr: String,
+        max_spread: Option<Decimal>,
+    },
+    /// Only contract itself can execute
+    /// Realize the cost of `lp_amount` withdrawn LP at the balances received since
+    /// `prev_stable_balance` and `prev_asset_balance`
+    RealizeMirrorLpHook {
+        mirror_asset_addr: String,
+        lp_amount: Uint128,
+        prev_stable_balance: Uint128,
+        prev_asset_balance: Uint128,
     },
     /// Claim MIR reward and swap to UST
-    CompoundMirror {},
-    CompoundMirrorHook {},
+    CompoundMirror { max_spread: Option<Decimal> },
+    CompoundMirrorHook { max_spread: Option<Decimal> },
+    /// Claim ANC reward, swap to UST and deposit it to anchor protocol
+    CompoundAnchor { max_spread: Option<Decimal> },
+    CompoundAnchorHook { max_spread: Option<Decimal> },
     /// Open short position
     OpenShortPosition {
         aterra_amount: Uint128,
@@ -73,19 +130,66 @@ pub enum ExecuteMsg {
     },
     CloseShortPosition {
         position_idx: Uint128,
+        max_spread: Option<Decimal>,
+    },
+    /// Burn part of the minted asset of a short position
+    ReduceShortPosition {
+        position_idx: Uint128,
+        burn_amount: Uint128,
+        max_spread: Option<Decimal>,
+    },
+    /// Withdraw aUST collateral of a short position down to the collateral ratio
+    WithdrawShortCollateral {
+        position_idx: Uint128,
+        collateral_ratio: Decimal,
+    },
+    /// Only contract itself can execute
+    WithdrawShortCollateralHook { prev_aterra_balance: Uint128 },
+    /// Mint mirror asset with aUST collateral and provide it to liquidity with `amount` UST,
+    /// hedging the liquidity with the minted debt
+    OpenDeltaNeutral {
+        mirror_asset_addr: String,
+        amount: Uint128,
+        collateral_ratio: Decimal,
+    },
+    /// Withdraw the hedged liquidity, repay the minted debt and withdraw the collateral
+    CloseDeltaNeutral {
+        position_idx: Uint128,
+        max_spread: Option<Decimal>,
+    },
+    /// Only contract itself can execute
+    CloseDeltaNeutralHook {
+        position_idx: Uint128,
+        max_spread: Option<Decimal>,
+    },
+    /// Anyone can execute for a bounty
+    /// Restore a short position below the target band by depositing aUST collateral,
+    /// or by burning part of the minted asset if aUST is not enough
+    CheckShortPosition {
+        position_idx: Uint128,
     },
-    // CheckShortPosition {
-    //     position_idx: Uint128,
-    //     collateral_ratio: Decimal,
-    //     mirror_asset_addr: String,
-    // },
+    /// Anyone can execute
+    /// Claim the short sale proceeds of positions past the mirror lockup period
+    UnlockShortProceeds {
+        position_idxs: Vec<Uint128>,
+    },
+    /// Anyone can execute for a bounty once per harvest interval
+    /// Compound pending mirror and anchor rewards and deploy the proceeds per the anchor allocation
+    Harvest {},
+    /// Only contract itself can execute
+    HarvestHook {},
     /// Withdraw all invested UST from anchor and mirror protocol
     WithdrawAll {},
-    /// Withdraw some UST from invested
-    /// TODO check
-    // WithdrawInvested {
-    //     amount: Uint128,
-    // },
+    /// Withdraw `amount` UST from invested, redeeming aUST first
+    /// and then unwinding mirror liquidity proportionally, and send it to vault
+    WithdrawInvested {
+        amount: Uint128,
+    },
+    /// Only contract itself can execute
+    /// Send `amount` UST to vault, or the idle balance if the unwinding returned less
+    WithdrawInvestedHook {
+        amount: Uint128,
+    },
     /// Send UST to vault
     WithdrawToVault {
         amount: Option<Uint128>,
@@ -105,8 +209,16 @@ pub enum QueryMsg {
     State {},
     /// Query total underlying balance in strategy
     TotalBalance {},
-    // /// Query idle underlying balance
-    // IdleBalance {},
+    /// Query total underlying balance net of the performance fee accrued on unrealized gain
+    NetTotalBalance {},
+    /// Query cost, value and profit of mirror liquidity by asset
+    MirrorPositions {},
+    /// Query health of short positions opened by the strategy
+    ShortPositions {},
+    /// Query mirror asset held in liquidity against minted debt by asset
+    NetDelta {},
+    /// Query underlying balance held idle and in each protocol
+    Balances {},
 }
 
 // We define a custom struct for each query response
@@ -116,14 +228,27 @@ pub struct ConfigResponse {
     pub controller: String,
     pub vault: String,
     pub performance_fee: Decimal,
+    pub insurance_fee: Decimal,
+    pub anchor_allocation: Decimal,
+    pub rebalance_band: Decimal,
+    pub max_spread: Decimal,
+    pub slippage_tolerance: Decimal,
+    pub oracle_price_band: Decimal,
+    pub short_ratio_low: Decimal,
+    pub short_ratio_high: Decimal,
+    pub keeper_bounty: Uint128,
+    pub harvest_bounty: Uint128,
+    pub harvest_interval: u64,
     pub stable_denom: String,
 
     pub anchor_market: String,
     pub aterra_contract: String,
+    pub anchor_token: String,
 
     pub mirror_token: String,
     pub mirror_staking: String,
     pub mirror_mint: String,
+    pub mirror_lock: String,
     pub mirror_oracle: String,
     pub terraswap_factory: String,
 }
@@ -132,4 +257,99 @@ pub struct ConfigResponse {
 pub struct State {
     pub anchor_deposited: Uint128,
     pub aterra_collateral: Uint128,
+    /// Net gain on which performance fee is already charged,
+    /// reset when a loss is reported to the vault
+    pub high_water_mark: Uint128,
+    /// Debt to the vault as of the last lookup, used once the vault no longer lists the strategy
+    pub vault_debt: Uint128,
+    /// Block time of the last harvest
+    pub last_harvest: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MirrorPositionInfo {
+    pub mirror_asset: String,
+    /// UST invested for the liquidity still held
+    pub cost: Uint128,
+    pub lp_amount: Uint128,
+    /// Current UST value of the liquidity still held
+    pub value: Uint128,
+    /// UST value of the liquidity withdrawn
+    pub realized_proceeds: Uint128,
+    /// UST invested for the liquidity withdrawn
+    pub realized_cost: Uint128,
+    /// Realized and unrealized profit, or loss if value is below cost
+    pub profit: Uint128,
+    pub loss: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MirrorPositionsResponse {
+    pub positions: Vec<MirrorPositionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShortPositionInfo {
+    pub position_idx: Uint128,
+    pub mirror_asset: String,
+    /// aUST collateral deposited when opening and by CheckShortPosition top-ups
+    pub opening_collateral: Uint128,
+    pub opening_collateral_ratio: Decimal,
+    pub opened_at: u64,
+    /// Current aUST collateral
+    pub collateral: Uint128,
+    /// Minted mirror asset amount
+    pub asset_amount: Uint128,
+    /// Current collateral ratio, valuing aUST at the anchor exchange rate
+    pub collateral_ratio: Decimal,
+    /// Min collateral ratio of the asset in mirror mint
+    pub min_collateral_ratio: Decimal,
+    /// Collateral ratio left before the position can be liquidated
+    pub liquidation_distance: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShortPositionsResponse {
+    pub positions: Vec<ShortPositionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NetDeltaInfo {
+    pub mirror_asset: String,
+    /// Mirror asset held in staked liquidity and idle balance
+    pub long_amount: Uint128,
+    /// Mirror asset minted by the registered positions
+    pub short_amount: Uint128,
+    /// Net exposure, either long or short
+    pub net_long: Uint128,
+    pub net_short: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MirrorBalanceInfo {
+    pub mirror_asset: String,
+    /// Staked LP amount
+    pub lp_amount: Uint128,
+    /// UST value of the staked liquidity at the mirror oracle price, as in TotalBalance
+    pub value: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalancesResponse {
+    /// UST held by the strategy
+    pub idle_balance: Uint128,
+    /// UST value of aUST held by the strategy
+    pub anchor_balance: Uint128,
+    /// Anchor balance net of the performance fee charged on redemption
+    pub anchor_balance_without_fee: Uint128,
+    pub mirror_balances: Vec<MirrorBalanceInfo>,
+    /// UST value of aUST deposited as collateral of short positions
+    pub short_collateral: Uint128,
+    /// Short sale proceeds held by the mirror lock
+    pub locked_proceeds: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NetDeltaResponse {
+    pub assets: Vec<NetDeltaInfo>,
 }
@@ -1,4 +1,5 @@
 pub mod common;
+pub mod lock;
 pub mod mint;
 pub mod oracle;
 pub mod staking;
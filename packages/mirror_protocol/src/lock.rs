@@ -0,0 +1,60 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Uint128;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub owner: String,
+    pub mint_contract: String,
+    pub base_denom: String,
+    pub lockup_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        owner: Option<String>,
+        mint_contract: Option<String>,
+        base_denom: Option<String>,
+        lockup_period: Option<u64>,
+    },
+    /// Lock the short sale proceeds of a position, only the mint contract can execute
+    LockPositionFundsHook {
+        position_idx: Uint128,
+        receiver: String,
+    },
+    /// Send the proceeds of positions past the lockup period to their receiver
+    UnlockPositionFunds {
+        positions_idx: Vec<Uint128>,
+    },
+    /// Release the locked proceeds of a closed position, only the mint contract can execute
+    ReleasePositionFunds {
+        position_idx: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    PositionLockInfo { position_idx: Uint128 },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub mint_contract: String,
+    pub base_denom: String,
+    pub lockup_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionLockInfoResponse {
+    pub idx: Uint128,
+    pub receiver: String,
+    pub locked_amount: Uint128,
+    pub unlock_time: u64,
+}